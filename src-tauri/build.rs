@@ -1,79 +1,256 @@
-use std::path::Path;
-use reqwest;
-use zip;
+use sha2::{Digest, Sha256};
+use std::env;
+use std::fs;
 use std::io::Cursor;
-use std::{fs};
+use std::path::{Path, PathBuf};
 
-fn main() {
+const DUCKDB_VERSION: &str = "1.1.1";
+
+// 官方发布包的 SHA-256，升级 DuckDB 版本时需要同步更新，格式为 (压缩包文件名, 校验值)。
+// 需要固定的压缩包：libduckdb-windows-amd64.zip、libduckdb-windows-arm64.zip、
+// libduckdb-linux-amd64.zip、libduckdb-linux-aarch64.zip、libduckdb-osx-universal.zip。
+// 没有固定校验值的压缩包不会被使用：构建失败并输出实际校验值，
+// 与 GitHub 发布页核对无误后填入此处，或通过该压缩包对应的环境变量为本次构建指定，
+// 例如 libduckdb-windows-amd64.zip 对应 DUCKDB_SHA256_WINDOWS_AMD64。
+const DUCKDB_SHA256: &[(&str, &str)] = &[];
 
+type BuildResult<T> = Result<T, Box<dyn std::error::Error>>;
+
+fn main() {
     // 如果`dist/`目录发生变化，就重新编译，这样可以避免不必要的变更。
     println!("cargo:rerun-if-changed=dist");
+    println!("cargo:rerun-if-env-changed=DUCKDB_ARCHIVE");
+    println!("cargo:rerun-if-env-changed=DUCKDB_CACHE_DIR");
+    println!("cargo:rerun-if-env-changed=DUCKDB_LIB_DIR");
+
+    if let Err(e) = copy_version_history() {
+        eprintln!("复制版本历史失败: {}", e);
+        std::process::exit(1);
+    }
+
+    // build.rs 运行在宿主机上，目标平台需要从 cargo 传入的环境变量判断
+    let target_os = env::var("CARGO_CFG_TARGET_OS").unwrap_or_default();
+    let target_arch = env::var("CARGO_CFG_TARGET_ARCH").unwrap_or_default();
+
+    let result = match linking_strategy() {
+        // 由 libduckdb-sys 从源码编译并链接
+        Ok(LinkingStrategy::Bundled) => Ok(()),
+        Ok(LinkingStrategy::System) => link_system_duckdb(),
+        Ok(LinkingStrategy::Download) => setup_downloaded_duckdb(&target_os, &target_arch),
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        eprintln!("准备 DuckDB 库失败: {}", e);
+        eprintln!(
+            "离线构建时可以通过 DUCKDB_ARCHIVE 指定已下载的 libduckdb 压缩包，\
+             或通过 DUCKDB_CACHE_DIR 指定存放压缩包的缓存目录"
+        );
+        std::process::exit(1);
+    }
+
+    // 允许 Tauri 运行其构建过程
+    tauri_build::build();
+}
 
+// 仓库根目录的 version.json 复制到 OUT_DIR 后再打包进应用，单独构建本 crate 时使用空的版本历史
+fn copy_version_history() -> BuildResult<()> {
+    let source = Path::new("../version.json");
+    println!("cargo:rerun-if-changed={}", source.display());
+    let destination = PathBuf::from(env::var("OUT_DIR")?).join("version.json");
+    if source.exists() {
+        fs::copy(source, destination)?;
+    } else {
+        fs::write(destination, "[]")?;
+    }
+    Ok(())
+}
+
+enum LinkingStrategy {
+    Bundled,
+    System,
+    Download,
+}
+
+// 对应 Cargo.toml 中的 duckdb-bundled / duckdb-system / duckdb-download，必须且只能启用一个
+fn linking_strategy() -> BuildResult<LinkingStrategy> {
+    let enabled: Vec<(&str, LinkingStrategy)> = [
+        ("CARGO_FEATURE_DUCKDB_BUNDLED", LinkingStrategy::Bundled),
+        ("CARGO_FEATURE_DUCKDB_SYSTEM", LinkingStrategy::System),
+        ("CARGO_FEATURE_DUCKDB_DOWNLOAD", LinkingStrategy::Download),
+    ]
+    .into_iter()
+    .filter(|(feature, _)| env::var_os(feature).is_some())
+    .collect();
+
+    let mut enabled = enabled.into_iter();
+    match (enabled.next(), enabled.next()) {
+        (Some((_, strategy)), None) => Ok(strategy),
+        (None, _) => {
+            Err("需要启用 duckdb-bundled、duckdb-system、duckdb-download 中的一个 feature".into())
+        }
+        (Some(_), Some(_)) => Err(
+            "duckdb-bundled、duckdb-system、duckdb-download 只能启用一个，\
+             切换时请同时使用 --no-default-features"
+                .into(),
+        ),
+    }
+}
+
+// 链接系统中已安装的 libduckdb，DUCKDB_LIB_DIR 未设置时由 libduckdb-sys 通过 pkg-config 查找
+fn link_system_duckdb() -> BuildResult<()> {
+    if let Ok(lib_dir) = env::var("DUCKDB_LIB_DIR") {
+        if !Path::new(&lib_dir).is_dir() {
+            return Err(format!("DUCKDB_LIB_DIR 指定的目录不存在: {}", lib_dir).into());
+        }
+        println!("cargo:rustc-link-search=native={}", lib_dir);
+    }
+    Ok(())
+}
+
+fn setup_downloaded_duckdb(target_os: &str, target_arch: &str) -> BuildResult<()> {
     // 区分不同的目标平台和架构
-    #[cfg(all(target_os = "windows", target_arch = "x86_64"))]
-    const DUCKDB_URL: &str = "https://github.com/duckdb/duckdb/releases/download/v1.1.1/libduckdb-windows-amd64.zip";
-
-    #[cfg(all(target_os = "windows", target_arch = "aarch64"))]
-    const DUCKDB_URL: &str = "https://github.com/duckdb/duckdb/releases/download/v1.1.1/libduckdb-windows-arm64.zip";
-
-    #[cfg(target_os = "windows")]
-    {
-        let out_dir = std::env::current_dir().unwrap();
-        let lib_path = if cfg!(target_arch = "x86_64") {
-            Path::new(&out_dir).join("lib/duckdb/windows_x86_64")
-        } else if cfg!(target_arch = "aarch64") {
-            Path::new(&out_dir).join("lib/duckdb/windows_aarch64")
-        } else {
-            panic!("不支持的 Windows 架构");
-        };
-
-        // 确保目标目录存在
-        fs::create_dir_all(&lib_path).unwrap_or_else(|e| {
-            panic!("创建目录失败: {:?}, 错误: {}", lib_path, e)
-        });
+    let (archive_name, lib_dir_name) = match (target_os, target_arch) {
+        ("windows", "x86_64") => ("libduckdb-windows-amd64.zip", "windows_x86_64"),
+        ("windows", "aarch64") => ("libduckdb-windows-arm64.zip", "windows_aarch64"),
+        ("linux", "x86_64") => ("libduckdb-linux-amd64.zip", "linux_x86_64"),
+        ("linux", "aarch64") => ("libduckdb-linux-aarch64.zip", "linux_aarch64"),
+        ("macos", _) => ("libduckdb-osx-universal.zip", "macos_universal"),
+        _ => {
+            return Err(format!(
+                "duckdb-download 不支持的目标平台: {}-{}",
+                target_os, target_arch
+            )
+            .into())
+        }
+    };
 
-        // 下载并解压DuckDB库
-        download_and_extract(DUCKDB_URL, &lib_path).unwrap_or_else(|e| {
-            panic!("下载和解压 DuckDB 库失败: {}, 错误: {}", DUCKDB_URL, e)
-        });
+    let out_dir = env::current_dir()?;
+    let lib_path = out_dir.join("lib/duckdb").join(lib_dir_name);
+    // 确保目标目录存在
+    fs::create_dir_all(&lib_path)
+        .map_err(|e| format!("创建目录失败: {:?}, 错误: {}", lib_path, e))?;
+
+    // 已解压过同一版本时直接复用，避免每次构建都下载
+    if !already_extracted(&lib_path, archive_name) {
+        let archive = load_archive(&out_dir, archive_name)?;
+        verify_checksum(&archive, archive_name)?;
+        extract(&archive, &lib_path)?;
+        if target_os == "windows" {
+            // move duckdb.dll to src-tauri/lib/windows_resource
+            let windows_resource_path = lib_path.join("../../windows_resource");
+            fs::copy(
+                lib_path.join("duckdb.dll"),
+                windows_resource_path.join("duckdb.dll"),
+            )?;
+        }
+        fs::write(extracted_marker(&lib_path), archive_name)?;
+    }
 
-        println!("cargo:rustc-link-search=native={}", lib_path.display());
+    println!("cargo:rustc-link-search=native={}", lib_path.display());
+    if target_os == "windows" {
         println!("cargo:rustc-link-lib=static=duckdb"); // 根据需要也可以使用静态链接
         println!("cargo:rustc-link-flag=/NODEFAULTLIB:libduckdb.lib");
-
+    } else {
+        // 开发时直接从解压目录加载动态库，打包时需要随应用一起分发
+        println!("cargo:rustc-link-lib=dylib=duckdb");
+        println!("cargo:rustc-link-arg=-Wl,-rpath,{}", lib_path.display());
     }
+    Ok(())
+}
 
+fn extracted_marker(lib_path: &Path) -> PathBuf {
+    lib_path.join(format!(".duckdb-{}", DUCKDB_VERSION))
+}
 
-    // 允许 Tauri 运行其构建过程
-    tauri_build::build();
+fn already_extracted(lib_path: &Path, archive_name: &str) -> bool {
+    fs::read_to_string(extracted_marker(lib_path))
+        .map(|content| content == archive_name)
+        .unwrap_or(false)
+        && ["duckdb.lib", "libduckdb.so", "libduckdb.dylib"]
+            .iter()
+            .any(|lib| lib_path.join(lib).exists())
 }
 
-#[cfg(all(target_os = "windows"))]
-fn download_and_extract(url: &str, lib_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    // Download ZIP file
-    let response = reqwest::blocking::get(url)?;
-    let bytes = response.bytes()?;
+// 依次尝试 DUCKDB_ARCHIVE、缓存目录，最后才从 GitHub 下载并写入缓存
+fn load_archive(out_dir: &Path, archive_name: &str) -> BuildResult<Vec<u8>> {
+    if let Ok(archive_path) = env::var("DUCKDB_ARCHIVE") {
+        println!("cargo:rerun-if-changed={}", archive_path);
+        return fs::read(&archive_path).map_err(|e| {
+            format!(
+                "读取 DUCKDB_ARCHIVE 指定的文件失败: {}, 错误: {}",
+                archive_path, e
+            )
+            .into()
+        });
+    }
 
-    // Use Cursor to convert Bytes into a readable stream
-    let mut cursor = Cursor::new(bytes);
+    let cache_dir = env::var("DUCKDB_CACHE_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|_| out_dir.join("lib/duckdb/cache"));
+    let cached_archive = cache_dir.join(DUCKDB_VERSION).join(archive_name);
+    if cached_archive.exists() {
+        return Ok(fs::read(&cached_archive)?);
+    }
 
-    // Create ZIP file
-    let zip_path = lib_path.join("duckdb.zip");
-    let mut dest = fs::File::create(&zip_path)?;
-    std::io::copy(&mut cursor, &mut dest)?;
+    let url = format!(
+        "https://github.com/duckdb/duckdb/releases/download/v{}/{}",
+        DUCKDB_VERSION, archive_name
+    );
+    let bytes = download(&url).map_err(|e| format!("下载 DuckDB 库失败: {}, 错误: {}", url, e))?;
+    // 校验通过后才写入缓存，避免损坏或被篡改的压缩包留在缓存中
+    verify_checksum(&bytes, archive_name)?;
+    if let Some(parent) = cached_archive.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&cached_archive, &bytes)?;
+    Ok(bytes)
+}
 
-    // Extract files
-    let file = fs::File::open(&zip_path)?;
-    let mut archive = zip::ZipArchive::new(file)?;
-    archive.extract(lib_path)?;
+fn download(url: &str) -> BuildResult<Vec<u8>> {
+    let response = reqwest::blocking::get(url)?.error_for_status()?;
+    Ok(response.bytes()?.to_vec())
+}
+
+// 校验值覆盖按压缩包区分，避免为一个平台指定的校验值被用于其他平台的压缩包
+fn checksum_env_var(archive_name: &str) -> String {
+    let platform = archive_name
+        .trim_start_matches("libduckdb-")
+        .trim_end_matches(".zip");
+    format!(
+        "DUCKDB_SHA256_{}",
+        platform.to_uppercase().replace(['-', '.'], "_")
+    )
+}
 
-    // Remove the ZIP file
-    fs::remove_file(zip_path)?;
+fn verify_checksum(archive: &[u8], archive_name: &str) -> BuildResult<()> {
+    let actual = format!("{:x}", Sha256::digest(archive));
+    let env_var = checksum_env_var(archive_name);
+    println!("cargo:rerun-if-env-changed={}", env_var);
+    let expected = env::var(&env_var).ok().or_else(|| {
+        DUCKDB_SHA256
+            .iter()
+            .find(|(name, _)| *name == archive_name)
+            .map(|(_, sha256)| sha256.to_string())
+    });
 
-    // move duckdb.dll to src-tauri/lib/windows_resource
-    let duckdb_dll_path = lib_path.join("duckdb.dll");
-    let windows_resource_path = Path::new(&lib_path).join("../../windows_resource");
-    fs::copy(duckdb_dll_path, windows_resource_path.join("duckdb.dll"))?;
+    match expected {
+        Some(expected) if expected.eq_ignore_ascii_case(&actual) => Ok(()),
+        Some(expected) => Err(format!(
+            "{} 校验失败，期望 SHA-256 {}，实际为 {}",
+            archive_name, expected, actual
+        )
+        .into()),
+        None => Err(format!(
+            "{} 未固定 SHA-256（实际为 {}），核对后请填入 build.rs 的 DUCKDB_SHA256 或设置 {} 环境变量",
+            archive_name, actual, env_var
+        )
+        .into()),
+    }
+}
 
+fn extract(archive: &[u8], lib_path: &Path) -> BuildResult<()> {
+    // Extract files
+    let mut zip_archive = zip::ZipArchive::new(Cursor::new(archive))?;
+    zip_archive.extract(lib_path)?;
     Ok(())
 }
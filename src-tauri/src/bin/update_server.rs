@@ -0,0 +1,131 @@
+// 局域网更新服务器：根据发布产物和 version.json 生成 latest.json，并通过 HTTP 提供下载。
+//
+//   update-server generate <产物目录> --base-url <下载地址前缀> [--version-json <路径>]
+//   update-server serve <产物目录> --base-url <下载地址前缀> [--bind 0.0.0.0:8080] [--version-json <路径>]
+use std::path::PathBuf;
+use std::process::ExitCode;
+use test_tauri_updater_lib::update_server::manifest::{
+    generate_manifest, latest_version_record, scan_artifacts,
+};
+use test_tauri_updater_lib::update_server::server::{
+    ServedFiles, UpdateFileServer, MANIFEST_FILE_NAME,
+};
+
+const USAGE: &str = "用法:
+  update-server generate <产物目录> --base-url <下载地址前缀> [--version-json <路径>]
+  update-server serve <产物目录> --base-url <下载地址前缀> [--bind 0.0.0.0:8080] [--version-json <路径>]";
+
+struct Options {
+    command: String,
+    dir: PathBuf,
+    bind: String,
+    base_url: Option<String>,
+    version_json: Option<PathBuf>,
+}
+
+fn parse_args() -> Result<Options, String> {
+    let mut args = std::env::args().skip(1);
+    let command = args.next().ok_or("缺少子命令")?;
+    let dir = args.next().map(PathBuf::from).ok_or("缺少产物目录")?;
+    let mut options = Options {
+        command,
+        dir,
+        bind: "0.0.0.0:8080".to_string(),
+        base_url: None,
+        version_json: None,
+    };
+
+    while let Some(flag) = args.next() {
+        let value = args
+            .next()
+            .ok_or_else(|| format!("参数 {} 缺少取值", flag))?;
+        match flag.as_str() {
+            "--bind" => options.bind = value,
+            "--base-url" => options.base_url = Some(value),
+            "--version-json" => options.version_json = Some(PathBuf::from(value)),
+            _ => return Err(format!("未知参数: {}", flag)),
+        }
+    }
+    Ok(options)
+}
+
+fn build_manifest(options: &Options, base_url: &str) -> Result<Vec<u8>, String> {
+    let version_json = options
+        .version_json
+        .clone()
+        .unwrap_or_else(|| options.dir.join("version.json"));
+    let record = latest_version_record(&version_json)?;
+    let artifacts = scan_artifacts(&options.dir)?;
+    if artifacts.is_empty() {
+        return Err(format!("{:?} 中没有找到带签名的安装包", options.dir));
+    }
+    for artifact in &artifacts {
+        println!("{} -> {}", artifact.target, artifact.file_name);
+    }
+    let manifest = generate_manifest(&record, &artifacts, base_url)?;
+    serde_json::to_vec_pretty(&manifest).map_err(|err| err.to_string())
+}
+
+fn run(options: Options) -> Result<(), String> {
+    match options.command.as_str() {
+        "generate" => {
+            let base_url = options.base_url.clone().ok_or("generate 需要 --base-url")?;
+            let manifest = build_manifest(&options, &base_url)?;
+            let output = options.dir.join(MANIFEST_FILE_NAME);
+            std::fs::write(&output, manifest)
+                .map_err(|err| format!("写入 {:?} 失败: {}", output, err))?;
+            println!("已生成 {:?}", output);
+            Ok(())
+        }
+        "serve" => {
+            // 监听地址通常是 0.0.0.0，客户端无法用它下载，必须指定局域网内可访问的下载地址前缀
+            let base_url = options.base_url.clone().ok_or("serve 需要 --base-url")?;
+            let manifest = build_manifest(&options, &base_url)?;
+            let server =
+                UpdateFileServer::start(&options.bind, ServedFiles::Directory(options.dir.clone()))
+                    .map_err(|err| format!("启动更新服务失败: {}", err))?;
+            server.set_manifest(manifest);
+            println!(
+                "更新服务已启动: {}/{}",
+                base_url.trim_end_matches('/'),
+                MANIFEST_FILE_NAME
+            );
+            server.wait();
+            Ok(())
+        }
+        command => Err(format!("未知子命令: {}", command)),
+    }
+}
+
+// 服务和清单生成通过 log 输出警告，独立运行时没有日志插件，直接输出到标准错误
+struct StderrLogger;
+
+impl log::Log for StderrLogger {
+    fn enabled(&self, metadata: &log::Metadata) -> bool {
+        metadata.level() <= log::Level::Info
+    }
+
+    fn log(&self, record: &log::Record) {
+        if self.enabled(record.metadata()) {
+            eprintln!("[{}] {}", record.level(), record.args());
+        }
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: StderrLogger = StderrLogger;
+
+fn main() -> ExitCode {
+    if log::set_logger(&LOGGER).is_ok() {
+        log::set_max_level(log::LevelFilter::Info);
+    }
+    let result = parse_args().and_then(run);
+    match result {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(err) => {
+            eprintln!("{}\n\n{}", err, USAGE);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -1,28 +1,145 @@
 // Learn more about Tauri commands at https://tauri.app/develop/calling-rust/
-use tauri::Manager;
+use std::sync::Arc;
+use tauri::{Emitter, Manager};
 
 mod register_handlers;
-use register_handlers::data_center::performance_evaluation::register_case_data_handler;
+use register_handlers::data_center::performance_evaluation::{
+    attach_external_dataset, cancel_data_center_operation, delete_case, detach_external_dataset,
+    download_case_attachment, export_case_data_parquet, get_case_attachment_status,
+    get_sync_status, import_case_data_export, instantiate_case_from_template, list_case_reports,
+    list_case_templates, list_data_center_operations, list_external_datasets, open_case_attachment,
+    push_case_edits, push_case_edits_to_export, register_case_data_handler, run_case_report,
+    run_sql_query, validate_case_content,
+};
+use register_handlers::diagnostics::{
+    dismiss_crash_reports, export_diagnostics_bundle, get_pending_crash_reports,
+};
+use register_handlers::logging::{export_recent_logs, get_log_level, set_log_level};
+use register_handlers::updater::{
+    check_for_update, defer_update, download_and_install_update, get_changelog,
+    get_database_recovery_status, get_update_settings, install_offline_update,
+    restore_database_snapshot, set_update_channel, set_update_channel_endpoints,
+    set_update_schedule, take_whats_new,
+};
 
 mod states;
+pub mod update_server;
+use states::crash;
+use states::data_center::operation::{OperationEvent, OperationInfo, OperationRegistry};
 use states::data_center::performance_evaluation::case_data::database::PerformanceEvaluationCaseDataState;
+use states::logging;
+use states::updater::backup::DatabaseRecoveryState;
+use states::updater::manager::UpdateManagerState;
+use states::updater::scheduler::spawn_update_scheduler;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
+        .plugin(logging::plugin())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
         .plugin(tauri_plugin_updater::Builder::new().build())
         .plugin(tauri_plugin_shell::init())
+        .plugin(tauri_plugin_opener::init())
         .plugin(tauri_plugin_clipboard_manager::init())
         .setup(|app| {
             let handler = app.handle();
+            logging::init(handler);
+            crash::install_panic_hook(handler);
+            let operation_handler = handler.clone();
+            app.manage(OperationRegistry::new(Arc::new(
+                move |event: OperationEvent, operation: &OperationInfo| {
+                    let _ = operation_handler.emit(
+                        "data_center_operation",
+                        serde_json::json!({
+                            "event": event,
+                            "operation": operation
+                        }),
+                    );
+                },
+            )));
             register_case_data_handler(handler);
-            let db = PerformanceEvaluationCaseDataState::new(handler);
-            app.manage(db);
+            let db_path = PerformanceEvaluationCaseDataState::database_path(handler).ok();
+            // 无法确定数据目录时不提供快照恢复，但不影响打开数据库
+            let recovery = DatabaseRecoveryState::new(handler, db_path)
+                .map_err(|err| log::error!("数据库恢复状态初始化失败: {:?}", err))
+                .ok();
+            // 数据库打开或迁移失败时不再 panic，由前端提示用户恢复更新前的快照
+            match PerformanceEvaluationCaseDataState::try_new(handler) {
+                Ok(db) => {
+                    if let Some(recovery) = &recovery {
+                        recovery.mark_verified(&handler.package_info().version.to_string());
+                    }
+                    let incident_handler = handler.clone();
+                    db.set_incident_reporter(Arc::new(move |incident| {
+                        let _ = incident_handler.emit("database_incident", incident);
+                    }));
+                    let sync_handler = handler.clone();
+                    db.set_sync_reporter(Arc::new(move |status| {
+                        let _ = sync_handler.emit("data_center_sync_progress", status);
+                    }));
+                    app.manage(db);
+                }
+                Err(err) => {
+                    log::error!("预算绩效管理案例库 - 数据库打开失败: {:?}", err);
+                    if let Some(recovery) = &recovery {
+                        recovery.mark_failed(err.to_string());
+                    }
+                }
+            }
+            if let Some(recovery) = recovery {
+                app.manage(recovery);
+            }
+            // 更新管理初始化失败时不影响应用启动，只是不再检查更新
+            match UpdateManagerState::new(handler) {
+                Ok(manager) => {
+                    app.manage(manager);
+                    spawn_update_scheduler(handler);
+                }
+                Err(err) => log::error!("更新管理初始化失败: {:?}", err),
+            }
             Ok(())
         })
-        .invoke_handler(tauri::generate_handler![])
+        .invoke_handler(tauri::generate_handler![
+            get_update_settings,
+            set_update_channel,
+            set_update_channel_endpoints,
+            check_for_update,
+            download_and_install_update,
+            set_update_schedule,
+            defer_update,
+            get_changelog,
+            take_whats_new,
+            get_database_recovery_status,
+            restore_database_snapshot,
+            install_offline_update,
+            list_case_templates,
+            instantiate_case_from_template,
+            validate_case_content,
+            get_case_attachment_status,
+            download_case_attachment,
+            open_case_attachment,
+            delete_case,
+            import_case_data_export,
+            push_case_edits,
+            push_case_edits_to_export,
+            get_sync_status,
+            cancel_data_center_operation,
+            list_data_center_operations,
+            list_case_reports,
+            run_case_report,
+            run_sql_query,
+            export_case_data_parquet,
+            attach_external_dataset,
+            list_external_datasets,
+            detach_external_dataset,
+            get_log_level,
+            set_log_level,
+            export_recent_logs,
+            export_diagnostics_bundle,
+            get_pending_crash_reports,
+            dismiss_crash_reports
+        ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
@@ -1,8 +1,93 @@
+use crate::states::data_center::error::ValidationIssue;
+use crate::states::data_center::operation::{
+    OperationInfo, OperationPolicy, OperationRegistry, OperationStart,
+};
+use crate::states::data_center::performance_evaluation::case_data::analytics::{
+    ReportDefinition, REPORTS,
+};
+use crate::states::data_center::performance_evaluation::case_data::attachment::CaseAttachment;
 use crate::states::data_center::performance_evaluation::case_data::database::{
     CustomError, PerformanceEvaluationCaseDataState,
 };
-use serde_json::{json, Value as JsonValue};
-use tauri::{AppHandle, Emitter, Listener, Manager};
+use crate::states::data_center::performance_evaluation::case_data::dataset::CaseRecord;
+use crate::states::data_center::performance_evaluation::case_data::exchange::ExternalDataset;
+use crate::states::data_center::performance_evaluation::case_template::dataset::CaseTemplateRecord;
+use crate::states::data_center::sync_status::DatasetSyncStatus;
+use crate::states::data_center::tabular::TabularResult;
+use serde_json::{json, Map, Value as JsonValue};
+use tauri::{AppHandle, Emitter, Listener, Manager, State};
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_opener::OpenerExt;
+
+const QUERY_CASE_DATA: &str = "query_case_data";
+const QUERY_CASE_TEMPLATE: &str = "query_case_template";
+
+// 发送给前端的错误信息
+fn error_message(err: &CustomError) -> String {
+    match err {
+        CustomError::DuckDBError(e) => format!("DuckDB error: {:?}", e),
+        CustomError::ReqwestError(_) => "Reqwest error".to_string(),
+        CustomError::JoinError(_) => "Join error".to_string(),
+        CustomError::IoError(_)
+        | CustomError::TauriError(_)
+        | CustomError::NotFound(_)
+        | CustomError::AlreadyExists(_)
+        | CustomError::ValidationError(_)
+        | CustomError::InvalidSchema(_)
+        | CustomError::Cancelled
+        | CustomError::OperationFailed
+        | CustomError::InvalidQuery(_)
+        | CustomError::Timeout(_)
+        | CustomError::InvalidAttachmentSource(_) => format!("{}", err),
+    }
+}
+
+// 结果中带上 operation_id，前端据此丢弃过期的结果
+fn with_operation_id(mut data: JsonValue, operation_id: &str) -> JsonValue {
+    if let Some(object) = data.as_object_mut() {
+        object.insert("operation_id".to_string(), json!(operation_id));
+    }
+    data
+}
+
+// 查询结果事件的内容：成功时是查询结果，失败时是 {operation_id, error}，同时发送 error 事件提示用户。
+// 失败时也要发送结果事件，前端据此结束该操作的加载状态
+fn result_payload(
+    app: &AppHandle,
+    result: Result<JsonValue, CustomError>,
+    operation_id: &str,
+) -> JsonValue {
+    match result {
+        Ok(data) => with_operation_id(data, operation_id),
+        Err(err) => {
+            let message = error_message(&err);
+            let _ = app.emit("error", Some(json!({"message": message})));
+            json!({"operation_id": operation_id, "error": message})
+        }
+    }
+}
+
+// 在独立任务中执行的操作不能借用命令参数中的 State，需要从 AppHandle 重新获取
+fn case_data_state(
+    app: &AppHandle,
+) -> Result<State<'_, PerformanceEvaluationCaseDataState>, CustomError> {
+    app.try_state::<PerformanceEvaluationCaseDataState>()
+        .ok_or_else(|| CustomError::NotFound("案例数据库不可用".to_string()))
+}
+
+// 事件参数无法解析时通知前端，不能让监听器 panic
+fn parse_payload(app: &AppHandle, payload: &str) -> Option<JsonValue> {
+    match payload.parse() {
+        Ok(payload) => Some(payload),
+        Err(err) => {
+            let _ = app.emit(
+                "error",
+                Some(json!({"message": format!("请求参数无效: {}", err)})),
+            );
+            None
+        }
+    }
+}
 
 pub fn register_case_data_handler(app: &AppHandle) {
     let app_clone = app.clone();
@@ -10,55 +95,40 @@ pub fn register_case_data_handler(app: &AppHandle) {
         "query_data_center_performance_evaluation_case_data",
         move |event| {
             let app_clone = app_clone.to_owned();
-            let payload = event.payload().to_owned();
-            // println!("payload: {:?}", payload);
-            tauri::async_runtime::spawn(async move {
-                let payload = payload.to_string();
-                let payload: JsonValue = payload.parse().unwrap();
-
-                let token = payload["token"].as_str().unwrap();
-                let project_type = payload["project_type"].as_str().unwrap();
-                let handler = app_clone.state::<PerformanceEvaluationCaseDataState>();
-                let data = handler
-                    .query_data_from_backend(token, project_type)
-                    .await
-                    .unwrap_or_else(|err| match err {
-                        CustomError::DuckDBError(e) => {
-                            app_clone
-                                .emit(
-                                    "error",
-                                    Some(json!({"message": format!("DuckDB error: {:?}", e)})),
-                                )
-                                .expect(
-                                    "data center performance evaluation case data handler error",
-                                );
-                            json!([])
-                        }
-                        CustomError::ReqwestError(_) => {
-                            app_clone
-                                .emit("error", Some(json!({"message": "Reqwest error"})))
-                                .expect(
-                                    "data center performance evaluation case data handler error",
-                                );
-                            json!([])
-                        }
-                        CustomError::JoinError(_) => {
-                            app_clone
-                                .emit("error", Some(json!({"message": "Join error"})))
-                                .expect(
-                                    "data center performance evaluation case data handler error",
-                                );
-                            json!([])
-                        }
-                    });
-                // println!("Data: {:?}", data);
-                app_clone
-                    .emit(
-                        "query_data_center_performance_evaluation_case_data_result",
-                        data,
-                    )
-                    .expect("data center performance evaluation case data handler error");
+            let Some(payload) = parse_payload(&app_clone, event.payload()) else {
+                return;
+            };
+            let token = payload["token"].as_str().unwrap_or("").to_string();
+            let project_type = payload["project_type"].as_str().unwrap_or("").to_string();
+
+            // 快速切换项目类型时取消之前的查询，相同项目类型的查询正在执行时不重复发起
+            let registry = app_clone.state::<OperationRegistry>();
+            let operation_id =
+                match registry.begin(QUERY_CASE_DATA, &project_type, OperationPolicy::Supersede) {
+                    OperationStart::Started(operation_id) => operation_id,
+                    OperationStart::Joined(_) => return,
+                };
+
+            let task_operation_id = operation_id.clone();
+            let handle = tauri::async_runtime::spawn(async move {
+                let operation_id = task_operation_id;
+                let registry = app_clone.state::<OperationRegistry>();
+                let guard = registry.guard(&operation_id);
+                let result = match app_clone.try_state::<PerformanceEvaluationCaseDataState>() {
+                    Some(handler) => handler.query_data_from_backend(&token, &project_type).await,
+                    None => Err(CustomError::NotFound("案例数据库不可用".to_string())),
+                };
+
+                guard.finish(|| {
+                    app_clone
+                        .emit(
+                            "query_data_center_performance_evaluation_case_data_result",
+                            result_payload(&app_clone, result, &operation_id),
+                        )
+                        .expect("data center performance evaluation case data handler error");
+                });
             });
+            registry.attach(&operation_id, handle);
         },
     );
 
@@ -67,24 +137,268 @@ pub fn register_case_data_handler(app: &AppHandle) {
         "query_data_center_performance_evaluation_case_template",
         move |event| {
             let app_clone = app_clone.clone();
-            let payload = event.payload().to_owned();
-            tauri::async_runtime::spawn(async move {
-                let payload: JsonValue = payload.to_string().parse().unwrap();
-                // println!("payload: {:?}", payload);
-                let token = payload["token"].as_str().unwrap();
-                let handler = app_clone.state::<PerformanceEvaluationCaseDataState>();
-
-                let result = handler
-                    .query_data_template_from_backend(token)
-                    .await
-                    .unwrap();
-                app_clone
-                    .emit(
-                        "query_data_center_performance_evaluation_case_template_result",
-                        result,
-                    )
-                    .expect("data center performance evaluation case template handler error");
+            let Some(payload) = parse_payload(&app_clone, event.payload()) else {
+                return;
+            };
+            let token = payload["token"].as_str().unwrap_or("").to_string();
+
+            let registry = app_clone.state::<OperationRegistry>();
+            let operation_id =
+                match registry.begin(QUERY_CASE_TEMPLATE, "", OperationPolicy::Supersede) {
+                    OperationStart::Started(operation_id) => operation_id,
+                    OperationStart::Joined(_) => return,
+                };
+
+            let task_operation_id = operation_id.clone();
+            let handle = tauri::async_runtime::spawn(async move {
+                let operation_id = task_operation_id;
+                let registry = app_clone.state::<OperationRegistry>();
+                let guard = registry.guard(&operation_id);
+                let result = match app_clone.try_state::<PerformanceEvaluationCaseDataState>() {
+                    Some(handler) => handler.query_data_template_from_backend(&token).await,
+                    None => Err(CustomError::NotFound("案例数据库不可用".to_string())),
+                };
+
+                guard.finish(|| {
+                    app_clone
+                        .emit(
+                            "query_data_center_performance_evaluation_case_template_result",
+                            result_payload(&app_clone, result, &operation_id),
+                        )
+                        .expect("data center performance evaluation case template handler error");
+                });
             });
+            registry.attach(&operation_id, handle);
         },
     );
 }
+
+#[tauri::command]
+pub async fn list_case_templates(
+    state: State<'_, PerformanceEvaluationCaseDataState>,
+    project_type: String,
+    token: Option<String>,
+) -> Result<Vec<CaseTemplateRecord>, CustomError> {
+    state.list_templates(&project_type, token.as_deref()).await
+}
+
+#[tauri::command]
+pub async fn instantiate_case_from_template(
+    state: State<'_, PerformanceEvaluationCaseDataState>,
+    template_id: i64,
+    project_name: String,
+) -> Result<CaseRecord, CustomError> {
+    state
+        .instantiate_case_from_template(template_id, &project_name)
+        .await
+}
+
+#[tauri::command]
+pub async fn validate_case_content(
+    state: State<'_, PerformanceEvaluationCaseDataState>,
+    project_type: String,
+    content: JsonValue,
+) -> Result<Vec<ValidationIssue>, CustomError> {
+    state.validate_case_content(&project_type, content).await
+}
+
+#[tauri::command]
+pub async fn get_case_attachment_status(
+    state: State<'_, PerformanceEvaluationCaseDataState>,
+    case_id: i64,
+) -> Result<CaseAttachment, CustomError> {
+    state.attachment_status(case_id).await
+}
+
+#[tauri::command]
+pub async fn download_case_attachment(
+    app: AppHandle,
+    registry: State<'_, OperationRegistry>,
+    case_id: i64,
+) -> Result<CaseAttachment, CustomError> {
+    registry
+        .run(
+            "download_case_attachment",
+            &case_id.to_string(),
+            async move { case_data_state(&app)?.download_attachment(case_id).await },
+        )
+        .await
+}
+
+#[tauri::command]
+pub async fn open_case_attachment(
+    app: AppHandle,
+    state: State<'_, PerformanceEvaluationCaseDataState>,
+    case_id: i64,
+) -> Result<(), CustomError> {
+    let path = state.attachment_path(case_id).await?;
+    // 使用系统默认程序打开附件
+    app.opener()
+        .open_path(path.to_string_lossy(), None::<&str>)
+        .map_err(|err| CustomError::IoError(std::io::Error::other(err.to_string())))?;
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn delete_case(
+    state: State<'_, PerformanceEvaluationCaseDataState>,
+    case_id: i64,
+) -> Result<(), CustomError> {
+    state.delete_case(case_id).await
+}
+
+// 从 U 盘等导出目录导入案例和模板，用于无法联网的单位
+#[tauri::command]
+pub async fn import_case_data_export(
+    app: AppHandle,
+    registry: State<'_, OperationRegistry>,
+    dir: String,
+) -> Result<JsonValue, CustomError> {
+    registry
+        .run("import_case_data_export", &dir.clone(), async move {
+            case_data_state(&app)?.import_from_export(dir.into()).await
+        })
+        .await
+}
+
+#[tauri::command]
+pub async fn push_case_edits(
+    app: AppHandle,
+    registry: State<'_, OperationRegistry>,
+    token: String,
+    case_ids: Vec<i64>,
+) -> Result<JsonValue, CustomError> {
+    registry
+        .run("push_case_edits", "", async move {
+            case_data_state(&app)?
+                .push_case_edits(&token, case_ids)
+                .await
+        })
+        .await
+}
+
+// 把本地编辑写入导出目录的 outbox，用于无法联网的单位
+#[tauri::command]
+pub async fn push_case_edits_to_export(
+    app: AppHandle,
+    registry: State<'_, OperationRegistry>,
+    dir: String,
+    case_ids: Vec<i64>,
+) -> Result<JsonValue, CustomError> {
+    registry
+        .run("push_case_edits_to_export", &dir.clone(), async move {
+            case_data_state(&app)?
+                .push_case_edits_to_export(dir.into(), case_ids)
+                .await
+        })
+        .await
+}
+
+#[tauri::command]
+pub fn get_sync_status(
+    state: State<'_, PerformanceEvaluationCaseDataState>,
+) -> Vec<DatasetSyncStatus> {
+    state.sync_status()
+}
+
+#[tauri::command]
+pub fn cancel_data_center_operation(
+    registry: State<'_, OperationRegistry>,
+    operation_id: String,
+) -> bool {
+    registry.cancel(&operation_id)
+}
+
+#[tauri::command]
+pub fn list_data_center_operations(registry: State<'_, OperationRegistry>) -> Vec<OperationInfo> {
+    registry.list()
+}
+
+// 可用的统计报表及其参数，前端据此生成筛选条件
+#[tauri::command]
+pub fn list_case_reports() -> Vec<ReportDefinition> {
+    REPORTS.to_vec()
+}
+
+#[tauri::command]
+pub async fn run_case_report(
+    state: State<'_, PerformanceEvaluationCaseDataState>,
+    report_id: String,
+    parameters: Option<Map<String, JsonValue>>,
+) -> Result<TabularResult, CustomError> {
+    state
+        .run_report(&report_id, parameters.unwrap_or_default())
+        .await
+}
+
+// 只读 SQL 查询，返回列信息和带类型的行
+#[tauri::command]
+pub async fn run_sql_query(
+    state: State<'_, PerformanceEvaluationCaseDataState>,
+    sql: String,
+    max_rows: Option<usize>,
+    timeout_secs: Option<u64>,
+) -> Result<TabularResult, CustomError> {
+    state.run_sql_query(sql, max_rows, timeout_secs).await
+}
+
+// 导出为 Parquet，sql 为空时导出整个案例库。导出位置由用户在保存对话框中选择，
+// 返回导出的文件和行数，用户取消保存时返回 None
+#[tauri::command]
+pub async fn export_case_data_parquet(
+    app: AppHandle,
+    registry: State<'_, OperationRegistry>,
+    sql: Option<String>,
+) -> Result<Option<JsonValue>, CustomError> {
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    app.dialog()
+        .file()
+        .set_file_name("cases.parquet")
+        .add_filter("parquet", &["parquet"])
+        .save_file(move |path| {
+            let _ = sender.send(path);
+        });
+    let Some(destination) = receiver
+        .await
+        .ok()
+        .flatten()
+        .and_then(|path| path.as_path().map(|path| path.to_path_buf()))
+    else {
+        return Ok(None);
+    };
+
+    let key = destination.to_string_lossy().to_string();
+    let rows = registry
+        .run("export_case_data_parquet", &key, async move {
+            case_data_state(&app)?
+                .export_parquet(sql, destination)
+                .await
+        })
+        .await?;
+    Ok(Some(json!({"path": key, "rows": rows})))
+}
+
+// 挂载后可以在 SQL 查询中通过 external.名称 与案例库联合查询
+#[tauri::command]
+pub async fn attach_external_dataset(
+    state: State<'_, PerformanceEvaluationCaseDataState>,
+    name: String,
+    path: String,
+) -> Result<ExternalDataset, CustomError> {
+    state.attach_external_dataset(name, path.into()).await
+}
+
+#[tauri::command]
+pub async fn list_external_datasets(
+    state: State<'_, PerformanceEvaluationCaseDataState>,
+) -> Result<Vec<ExternalDataset>, CustomError> {
+    state.list_external_datasets().await
+}
+
+#[tauri::command]
+pub async fn detach_external_dataset(
+    state: State<'_, PerformanceEvaluationCaseDataState>,
+    name: String,
+) -> Result<(), CustomError> {
+    state.detach_external_dataset(name).await
+}
@@ -0,0 +1,23 @@
+use crate::states::crash::{self, CrashError, CrashReport};
+use crate::states::diagnostics::{self, DiagnosticsError};
+use tauri::AppHandle;
+
+// 导出诊断包，用户取消保存时返回 None。全部崩溃报告会一并打包
+#[tauri::command]
+pub async fn export_diagnostics_bundle(app: AppHandle) -> Result<Option<String>, DiagnosticsError> {
+    Ok(diagnostics::export_bundle(&app)
+        .await?
+        .map(|path| path.to_string_lossy().to_string()))
+}
+
+// 前端启动后调用，有崩溃报告时提示用户查看或导出诊断包
+#[tauri::command]
+pub fn get_pending_crash_reports(app: AppHandle) -> Result<Vec<CrashReport>, CrashError> {
+    crash::pending_reports(&app)
+}
+
+// id 为空时删除全部崩溃报告
+#[tauri::command]
+pub fn dismiss_crash_reports(app: AppHandle, id: Option<String>) -> Result<(), CrashError> {
+    crash::dismiss_reports(&app, id.as_deref())
+}
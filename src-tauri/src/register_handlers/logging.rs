@@ -0,0 +1,27 @@
+use crate::states::logging::{self, LoggingError};
+use tauri::AppHandle;
+
+const DEFAULT_EXPORT_LINES: usize = 2000;
+
+#[tauri::command]
+pub fn get_log_level() -> String {
+    logging::level()
+}
+
+#[tauri::command]
+pub fn set_log_level(level: String) -> Result<(), LoggingError> {
+    logging::set_level(&level)
+}
+
+// 导出最近的日志，返回保存的路径，用户取消保存时返回 None
+#[tauri::command]
+pub async fn export_recent_logs(
+    app: AppHandle,
+    max_lines: Option<usize>,
+) -> Result<Option<String>, LoggingError> {
+    Ok(
+        logging::export_recent_logs(&app, max_lines.unwrap_or(DEFAULT_EXPORT_LINES))
+            .await?
+            .map(|path| path.to_string_lossy().to_string()),
+    )
+}
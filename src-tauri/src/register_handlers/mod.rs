@@ -1 +1,4 @@
 pub mod data_center;
+pub mod diagnostics;
+pub mod logging;
+pub mod updater;
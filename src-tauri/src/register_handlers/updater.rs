@@ -0,0 +1,101 @@
+use crate::states::updater::backup::{DatabaseRecoveryState, DatabaseRecoveryStatus};
+use crate::states::updater::changelog::Changelog;
+use crate::states::updater::channel::{QuietHours, UpdateChannel, UpdateSettings};
+use crate::states::updater::manager::{UpdateInfo, UpdateManagerState, UpdaterError};
+use crate::states::updater::offline::OfflineUpdatePackage;
+use tauri::{AppHandle, State};
+
+#[tauri::command]
+pub fn get_update_settings(manager: State<'_, UpdateManagerState>) -> UpdateSettings {
+    manager.settings()
+}
+
+#[tauri::command]
+pub async fn set_update_channel(
+    manager: State<'_, UpdateManagerState>,
+    channel: UpdateChannel,
+    allow_downgrade: bool,
+) -> Result<UpdateSettings, UpdaterError> {
+    manager.set_channel(channel, allow_downgrade).await
+}
+
+#[tauri::command]
+pub fn set_update_channel_endpoints(
+    manager: State<'_, UpdateManagerState>,
+    channel: UpdateChannel,
+    endpoints: Vec<String>,
+) -> Result<UpdateSettings, UpdaterError> {
+    manager.set_channel_endpoints(channel, endpoints)
+}
+
+#[tauri::command]
+pub async fn check_for_update(
+    manager: State<'_, UpdateManagerState>,
+) -> Result<Option<UpdateInfo>, UpdaterError> {
+    manager.check().await
+}
+
+#[tauri::command]
+pub async fn download_and_install_update(
+    manager: State<'_, UpdateManagerState>,
+) -> Result<(), UpdaterError> {
+    manager.download_and_install().await
+}
+
+#[tauri::command]
+pub fn set_update_schedule(
+    manager: State<'_, UpdateManagerState>,
+    check_interval_minutes: u64,
+    quiet_hours: Option<QuietHours>,
+) -> Result<UpdateSettings, UpdaterError> {
+    manager.set_schedule(check_interval_minutes, quiet_hours)
+}
+
+#[tauri::command]
+pub fn defer_update(
+    manager: State<'_, UpdateManagerState>,
+    version: String,
+    minutes: u64,
+) -> Result<UpdateSettings, UpdaterError> {
+    manager.defer(version, minutes)
+}
+
+#[tauri::command]
+pub fn get_changelog(
+    manager: State<'_, UpdateManagerState>,
+    target_version: String,
+) -> Result<Changelog, UpdaterError> {
+    manager.changelog_to(&target_version)
+}
+
+#[tauri::command]
+pub fn take_whats_new(
+    manager: State<'_, UpdateManagerState>,
+) -> Result<Option<Changelog>, UpdaterError> {
+    manager.take_whats_new()
+}
+
+#[tauri::command]
+pub fn get_database_recovery_status(
+    recovery: State<'_, DatabaseRecoveryState>,
+) -> DatabaseRecoveryStatus {
+    recovery.status()
+}
+
+#[tauri::command]
+pub fn restore_database_snapshot(
+    app: AppHandle,
+    recovery: State<'_, DatabaseRecoveryState>,
+) -> Result<(), UpdaterError> {
+    recovery.restore_snapshot()?;
+    // 数据库连接在启动时建立，恢复后需要重启应用
+    app.restart()
+}
+
+#[tauri::command]
+pub async fn install_offline_update(
+    manager: State<'_, UpdateManagerState>,
+    package: OfflineUpdatePackage,
+) -> Result<UpdateInfo, UpdaterError> {
+    manager.install_offline(package).await
+}
@@ -0,0 +1,221 @@
+use crate::states::logging;
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use std::backtrace::Backtrace;
+use std::cell::Cell;
+use std::panic::PanicHookInfo;
+use std::path::{Path, PathBuf};
+use std::sync::OnceLock;
+use tauri::{AppHandle, Manager};
+use thiserror::Error;
+
+const CRASH_DIR_NAME: &str = "crashes";
+// 崩溃报告中附带的日志行数，只从当前日志文件末尾读取，避免在 panic hook 中读取大量文件
+const CRASH_LOG_LINES: usize = 200;
+const CRASH_LOG_BYTES: u64 = 64 * 1024;
+// 只保留最近的几份崩溃报告
+const MAX_CRASH_REPORTS: usize = 10;
+
+#[derive(Error, Debug)]
+pub enum CrashError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Tauri error: {0}")]
+    TauriError(#[from] tauri::Error),
+    #[error("JSON error: {0}")]
+    JsonError(#[from] serde_json::Error),
+    #[error("Crash report not found: {0}")]
+    NotFound(String),
+}
+
+// 命令返回的错误需要能序列化给前端
+impl Serialize for CrashError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CrashReport {
+    // 崩溃文件名（不含扩展名）
+    pub id: String,
+    pub time: String,
+    pub app_version: String,
+    pub thread: Option<String>,
+    pub message: String,
+    pub location: Option<String>,
+    pub backtrace: String,
+    pub recent_log: Vec<String>,
+}
+
+thread_local! {
+    // 防止写崩溃文件的过程中再次 panic 时重复进入；其他线程同时 panic 时各自写入报告
+    static IN_PANIC_HOOK: Cell<bool> = const { Cell::new(false) };
+}
+
+// 本次启动的时间，之后产生的崩溃报告留到下次启动时再提示
+static LAUNCH_TIME: OnceLock<DateTime<Local>> = OnceLock::new();
+
+pub fn crash_dir(app_handle: &AppHandle) -> Result<PathBuf, CrashError> {
+    Ok(app_handle.path().app_log_dir()?.join(CRASH_DIR_NAME))
+}
+
+// 在原有 panic hook 之前把崩溃信息写入日志目录下的 crashes 目录，下次启动时由前端提示用户查看。
+// 后台任务中的 panic 不会结束应用，同样会留下崩溃报告。
+pub fn install_panic_hook(app_handle: &AppHandle) {
+    let _ = LAUNCH_TIME.set(Local::now());
+    let app_version = app_handle.package_info().version.to_string();
+    let log_dir = app_handle.path().app_log_dir().ok();
+    let previous = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        if !IN_PANIC_HOOK.with(|in_hook| in_hook.replace(true)) {
+            // 先写崩溃文件再记录日志，日志写入出错时也不会丢失崩溃报告
+            let recent_log = log_dir
+                .as_deref()
+                .map(|log_dir| logging::current_log_tail(log_dir, CRASH_LOG_BYTES, CRASH_LOG_LINES))
+                .unwrap_or_default();
+            let report = build_report(&app_version, info, recent_log);
+            let written = match &log_dir {
+                Some(log_dir) => write_report(&log_dir.join(CRASH_DIR_NAME), &report),
+                None => Err(CrashError::NotFound("日志目录".to_string())),
+            };
+            log::error!(
+                crash_id = report.id.as_str();
+                "程序崩溃: {} ({})",
+                report.message,
+                report.location.as_deref().unwrap_or("未知位置")
+            );
+            if let Err(err) = written {
+                log::error!("写入崩溃报告失败: {:?}", err);
+            }
+            IN_PANIC_HOOK.with(|in_hook| in_hook.set(false));
+        }
+        previous(info);
+    }));
+}
+
+fn build_report(app_version: &str, info: &PanicHookInfo, recent_log: Vec<String>) -> CrashReport {
+    let message = if let Some(message) = info.payload().downcast_ref::<&str>() {
+        message.to_string()
+    } else if let Some(message) = info.payload().downcast_ref::<String>() {
+        message.clone()
+    } else {
+        "未知错误".to_string()
+    };
+    let now = chrono::Local::now();
+    CrashReport {
+        id: format!(
+            "crash-{}-{}",
+            now.format("%Y%m%d-%H%M%S"),
+            &uuid::Uuid::new_v4().simple().to_string()[..8]
+        ),
+        time: now.to_rfc3339(),
+        app_version: app_version.to_string(),
+        thread: std::thread::current().name().map(str::to_string),
+        message,
+        location: info
+            .location()
+            .map(|location| format!("{}:{}", location.file(), location.line())),
+        backtrace: Backtrace::force_capture().to_string(),
+        recent_log,
+    }
+}
+
+fn write_report(dir: &Path, report: &CrashReport) -> Result<(), CrashError> {
+    std::fs::create_dir_all(dir)?;
+    std::fs::write(
+        dir.join(format!("{}.json", report.id)),
+        serde_json::to_vec_pretty(report)?,
+    )?;
+    prune_reports(dir)?;
+    Ok(())
+}
+
+// 崩溃报告文件，按文件名（即时间）从旧到新排列
+fn report_files(dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    if !dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path
+            .extension()
+            .is_some_and(|extension| extension == "json")
+        {
+            files.push(path);
+        }
+    }
+    files.sort();
+    Ok(files)
+}
+
+fn prune_reports(dir: &Path) -> std::io::Result<()> {
+    let files = report_files(dir)?;
+    let excess = files.len().saturating_sub(MAX_CRASH_REPORTS);
+    for path in &files[..excess] {
+        std::fs::remove_file(path)?;
+    }
+    Ok(())
+}
+
+// 全部崩溃报告，最新的在前。无法解析的文件直接跳过
+pub fn all_reports(app_handle: &AppHandle) -> Result<Vec<CrashReport>, CrashError> {
+    read_reports(&crash_dir(app_handle)?)
+}
+
+fn read_reports(dir: &Path) -> Result<Vec<CrashReport>, CrashError> {
+    let mut reports = Vec::new();
+    for path in report_files(dir)?.iter().rev() {
+        match serde_json::from_slice(&std::fs::read(path)?) {
+            Ok(report) => reports.push(report),
+            Err(err) => log::warn!("无法读取崩溃报告 {:?}: {:?}", path, err),
+        }
+    }
+    Ok(reports)
+}
+
+// 本次启动之前尚未处理的崩溃报告。本次运行中后台任务的 panic 不会结束应用，留到下次启动时提示
+fn before_launch(report: &CrashReport) -> bool {
+    let Some(launch_time) = LAUNCH_TIME.get() else {
+        return true;
+    };
+    DateTime::parse_from_rfc3339(&report.time)
+        .ok()
+        .is_none_or(|time| time < *launch_time)
+}
+
+pub fn pending_reports(app_handle: &AppHandle) -> Result<Vec<CrashReport>, CrashError> {
+    Ok(all_reports(app_handle)?
+        .into_iter()
+        .filter(before_launch)
+        .collect())
+}
+
+// 用户查看或导出后删除崩溃报告，id 为空时删除全部已提示的报告
+pub fn dismiss_reports(app_handle: &AppHandle, id: Option<&str>) -> Result<(), CrashError> {
+    let dir = crash_dir(app_handle)?;
+    match id {
+        Some(id) => {
+            let path = dir.join(format!("{}.json", id));
+            // id 来自前端，只允许删除 crashes 目录下的报告
+            if id.contains(['/', '\\']) || !path.exists() {
+                return Err(CrashError::NotFound(id.to_string()));
+            }
+            std::fs::remove_file(path)?;
+        }
+        // 只删除已经提示过的报告
+        None => {
+            for report in read_reports(&dir)?
+                .iter()
+                .filter(|report| before_launch(report))
+            {
+                std::fs::remove_file(dir.join(format!("{}.json", report.id)))?;
+            }
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,236 @@
+use crate::states::data_center::error::CustomError;
+use duckdb::Connection;
+use serde::Serialize;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Duration;
+use tokio::task;
+
+// 写锁被 panic 的任务污染后的处理结果，上报给前端
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseIncident {
+    pub message: String,
+    // 连接是否可以继续使用
+    pub recovered: bool,
+    // 是否重新打开了数据库连接
+    pub reopened: bool,
+}
+
+pub type IncidentReporter = Arc<dyn Fn(&DatabaseIncident) + Send + Sync>;
+
+struct Connections {
+    writer: Mutex<Connection>,
+    // 仅用于克隆读连接，避免克隆时等待写锁
+    reader_source: Mutex<Connection>,
+    // 内存数据库没有路径，无法重新打开
+    path: Option<PathBuf>,
+    incident_reporter: Mutex<Option<IncidentReporter>>,
+}
+
+// DuckDB 连接管理：读操作使用克隆出来的独立连接并发执行，写操作通过同一个连接串行执行，
+// 长时间的同步写入不会阻塞界面上的查询。
+pub struct ConnectionManager {
+    connections: Arc<Connections>,
+}
+
+impl ConnectionManager {
+    pub fn open(path: &Path) -> Result<Self, CustomError> {
+        Self::from_connection(Connection::open(path)?, Some(path.to_path_buf()))
+    }
+
+    // 不落盘的数据库，测试中使用
+    pub fn open_in_memory() -> Result<Self, CustomError> {
+        Self::from_connection(Connection::open_in_memory()?, None)
+    }
+
+    pub fn from_connection(db: Connection, path: Option<PathBuf>) -> Result<Self, CustomError> {
+        let reader_source = db.try_clone()?;
+        Ok(Self {
+            connections: Arc::new(Connections {
+                writer: Mutex::new(db),
+                reader_source: Mutex::new(reader_source),
+                path,
+                incident_reporter: Mutex::new(None),
+            }),
+        })
+    }
+
+    pub fn set_incident_reporter(&self, reporter: IncidentReporter) {
+        *self
+            .connections
+            .incident_reporter
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner) = Some(reporter);
+    }
+
+    fn reader(&self) -> Result<Connection, CustomError> {
+        let reader_source = self
+            .connections
+            .reader_source
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        Ok(reader_source.try_clone()?)
+    }
+
+    // 在阻塞线程池中使用独立的读连接执行查询
+    pub async fn read<T, F>(&self, f: F) -> Result<T, CustomError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, CustomError> + Send + 'static,
+    {
+        let db = self.reader()?;
+        task::spawn_blocking(move || f(&db)).await?
+    }
+
+    // 与 read 相同，但超过 timeout 后中断该读连接上的查询，等查询停止后返回 CustomError::Timeout，
+    // 超时的查询不会继续占用读连接和 CPU
+    pub async fn read_with_timeout<T, F>(&self, timeout: Duration, f: F) -> Result<T, CustomError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, CustomError> + Send + 'static,
+    {
+        let db = self.reader()?;
+        let interrupt = db.interrupt_handle();
+        let mut query = task::spawn_blocking(move || f(&db));
+        if let Ok(result) = tokio::time::timeout(timeout, &mut query).await {
+            return result?;
+        }
+        // 中断只对正在执行的语句生效，f 中后续的语句可能刚好开始，所以持续中断直到任务结束
+        loop {
+            interrupt.interrupt();
+            if let Ok(result) = tokio::time::timeout(Duration::from_millis(100), &mut query).await {
+                let _ = result?;
+                return Err(CustomError::Timeout(timeout.as_secs()));
+            }
+        }
+    }
+
+    // 在阻塞线程池中持有写锁执行，所有写操作按顺序执行
+    pub async fn write<T, F>(&self, f: F) -> Result<T, CustomError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Connection) -> Result<T, CustomError> + Send + 'static,
+    {
+        let connections = Arc::clone(&self.connections);
+        task::spawn_blocking(move || {
+            let db = connections.lock_writer()?;
+            f(&db)
+        })
+        .await?
+    }
+
+    // 启动阶段（迁移、建表）直接在当前线程上使用写连接
+    pub fn write_blocking<T, F>(&self, f: F) -> Result<T, CustomError>
+    where
+        F: FnOnce(&Connection) -> Result<T, CustomError>,
+    {
+        let db = self.connections.lock_writer()?;
+        f(&db)
+    }
+}
+
+impl Connections {
+    // 某个写操作 panic 后写锁会被污染。此时检查连接是否仍然可用，不可用则重新打开数据库，
+    // 并清除污染标记，避免之后所有查询都跟着 panic。
+    fn lock_writer(&self) -> Result<MutexGuard<'_, Connection>, CustomError> {
+        let poisoned = match self.writer.lock() {
+            Ok(guard) => return Ok(guard),
+            Err(poisoned) => poisoned,
+        };
+
+        let mut guard = poisoned.into_inner();
+        self.writer.clear_poison();
+
+        // 回滚 panic 时可能未结束的事务，失败说明当前没有事务，可以忽略
+        let _ = guard.execute_batch("ROLLBACK;");
+        if is_healthy(&guard) {
+            self.report(DatabaseIncident {
+                message: "数据库写操作异常中断，连接检查正常，已恢复".to_string(),
+                recovered: true,
+                reopened: false,
+            });
+            return Ok(guard);
+        }
+
+        match self.reopen(&mut guard) {
+            Ok(()) => {
+                self.report(DatabaseIncident {
+                    message: "数据库写操作异常中断，连接不可用，已重新打开数据库".to_string(),
+                    recovered: true,
+                    reopened: true,
+                });
+                Ok(guard)
+            }
+            Err(err) => {
+                self.report(DatabaseIncident {
+                    message: format!("数据库写操作异常中断，重新打开数据库失败: {}", err),
+                    recovered: false,
+                    reopened: false,
+                });
+                Err(err)
+            }
+        }
+    }
+
+    fn reopen(&self, writer: &mut Connection) -> Result<(), CustomError> {
+        let path = self.path.as_ref().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "内存数据库无法重新打开")
+        })?;
+        let mut reader_source = self
+            .reader_source
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+
+        // 先用内存连接替换旧连接，释放对数据库文件的占用后再重新打开
+        *writer = Connection::open_in_memory()?;
+        *reader_source = writer.try_clone()?;
+        *writer = Connection::open(path)?;
+        *reader_source = writer.try_clone()?;
+        Ok(())
+    }
+
+    fn report(&self, incident: DatabaseIncident) {
+        log::error!("数据库连接异常: {:?}", incident);
+        let reporter = self
+            .incident_reporter
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        if let Some(reporter) = reporter {
+            reporter(&incident);
+        }
+    }
+}
+
+fn is_healthy(db: &Connection) -> bool {
+    db.query_row("SELECT 1", [], |row| row.get::<usize, i64>(0))
+        .map(|value| value == 1)
+        .unwrap_or(false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_with_timeout_interrupts_query() {
+        let manager = ConnectionManager::open_in_memory().unwrap();
+        let started = std::time::Instant::now();
+        let result = tauri::async_runtime::block_on(manager.read_with_timeout(
+            Duration::from_secs(1),
+            |db| {
+                Ok(db.query_row(
+                    "SELECT count(*) FROM range(1000000000000) a, range(1000) b",
+                    [],
+                    |row| row.get::<usize, i64>(0),
+                )?)
+            },
+        ));
+        assert!(matches!(result, Err(CustomError::Timeout(1))));
+        // 查询被中断后才返回，不会等到查询执行完
+        assert!(started.elapsed() < Duration::from_secs(30));
+
+        let value = tauri::async_runtime::block_on(manager.read(|db| Ok(is_healthy(db)))).unwrap();
+        assert!(value);
+    }
+}
@@ -0,0 +1,454 @@
+use crate::states::data_center::connection::ConnectionManager;
+use crate::states::data_center::error::CustomError;
+use crate::states::data_center::sync_status::SyncTracker;
+use chrono::{DateTime, FixedOffset};
+use duckdb::{params, params_from_iter, Connection, ToSql};
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+use std::future::Future;
+use std::marker::PhantomData;
+use std::sync::Arc;
+use std::time::Instant;
+
+// 数据中心的一个数据集：一张本地表和对应的行类型。
+// 新的数据集只需要实现该 trait，即可复用 DatasetRepository 的存储、同步和查询逻辑。
+// 表结构由数据库迁移创建和变更，这里只描述已有的表。
+pub trait Dataset: Send + Sync + 'static {
+    // 用于日志输出的名称
+    const NAME: &'static str;
+    const TABLE_NAME: &'static str;
+    // 后端的增量同步接口，未单独配置时使用
+    const SYNC_ENDPOINT: &'static str;
+    // 表的所有列，第一列必须是自增主键 id
+    const COLUMNS: &'static [&'static str];
+    // 唯一约束列，同步时按这些列做 upsert
+    const CONFLICT_COLUMNS: &'static [&'static str];
+    // 增量同步依据的时间列
+    const UPDATE_TIME_COLUMN: &'static str;
+    // 冲突时是否覆盖的附加条件，例如只接受版本号不低于本地的数据
+    const UPSERT_CONDITION: Option<&'static str> = None;
+
+    type Row: Serialize + Send + 'static;
+
+    // 校验并转换后端或前端传入的数据
+    fn from_json(value: &JsonValue) -> Result<Self::Row, CustomError>;
+    // 按 COLUMNS 的顺序读取一行
+    fn from_db_row(row: &duckdb::Row) -> Result<Self::Row, duckdb::Error>;
+    // 按 COLUMNS 的顺序生成插入参数，id 为空时自动分配
+    fn to_params(row: &Self::Row) -> Vec<Box<dyn ToSql>>;
+}
+
+// 写入前的校验，在写连接上执行，可以查询同一数据库中的其他表
+pub type RowValidator<R> = Arc<dyn Fn(&Connection, &R) -> Result<(), CustomError> + Send + Sync>;
+
+pub struct SyncOutcome {
+    // 后端返回的状态，0 表示成功
+    pub status: i64,
+    pub message: String,
+    // status 不为 0 时原样保留后端响应
+    pub response: JsonValue,
+    pub received: usize,
+    pub applied: usize,
+    // 未通过校验或写入失败的数据及原因
+    pub rejected: Vec<String>,
+}
+
+pub struct DatasetRepository<D: Dataset> {
+    db: Arc<ConnectionManager>,
+    validator: Option<RowValidator<D::Row>>,
+    tracker: Option<Arc<SyncTracker>>,
+    _dataset: PhantomData<D>,
+}
+
+impl<D: Dataset> Clone for DatasetRepository<D> {
+    fn clone(&self) -> Self {
+        Self {
+            db: Arc::clone(&self.db),
+            validator: self.validator.clone(),
+            tracker: self.tracker.clone(),
+            _dataset: PhantomData,
+        }
+    }
+}
+
+impl<D: Dataset> DatasetRepository<D> {
+    pub fn new(db: Arc<ConnectionManager>) -> Self {
+        Self {
+            db,
+            validator: None,
+            tracker: None,
+            _dataset: PhantomData,
+        }
+    }
+
+    // 同步过程中的各个阶段记录到 tracker 中
+    pub fn with_tracker(mut self, tracker: Arc<SyncTracker>) -> Self {
+        tracker.register(D::NAME);
+        self.tracker = Some(tracker);
+        self
+    }
+
+    fn track(&self, f: impl FnOnce(&SyncTracker)) {
+        if let Some(tracker) = &self.tracker {
+            f(tracker);
+        }
+    }
+
+    // 同步、导入和本地编辑都经过 upsert，校验在这里统一执行
+    pub fn with_validator(mut self, validator: RowValidator<D::Row>) -> Self {
+        self.validator = Some(validator);
+        self
+    }
+
+    fn select_sql(condition: &str) -> String {
+        format!(
+            "SELECT {} FROM {} {}",
+            D::COLUMNS.join(", "),
+            D::TABLE_NAME,
+            condition
+        )
+    }
+
+    fn upsert_sql() -> String {
+        let id_column = D::COLUMNS[0];
+        // 未传入 id 时，在当前最大 id 的基础上加 1
+        let values: Vec<String> = D::COLUMNS
+            .iter()
+            .enumerate()
+            .map(|(index, _)| {
+                if index == 0 {
+                    format!(
+                        "coalesce(?, (SELECT coalesce(max({}), 0) + 1 FROM {}))",
+                        id_column,
+                        D::TABLE_NAME
+                    )
+                } else {
+                    "?".to_string()
+                }
+            })
+            .collect();
+        let updates: Vec<String> = D::COLUMNS
+            .iter()
+            .skip(1)
+            .filter(|column| !D::CONFLICT_COLUMNS.contains(column))
+            .map(|column| format!("{} = excluded.{}", column, column))
+            .collect();
+
+        let condition = D::UPSERT_CONDITION
+            .map(|condition| format!(" WHERE {}", condition))
+            .unwrap_or_default();
+
+        format!(
+            "INSERT INTO {} ({}) VALUES ({}) ON CONFLICT ({}) DO UPDATE SET {}{} RETURNING {};",
+            D::TABLE_NAME,
+            D::COLUMNS.join(", "),
+            values.join(", "),
+            D::CONFLICT_COLUMNS.join(", "),
+            updates.join(", "),
+            condition,
+            D::COLUMNS.join(", ")
+        )
+    }
+
+    pub async fn newest_update_time(&self) -> Result<Option<String>, CustomError> {
+        self.db
+            .read(|db| {
+                let mut stmt = db.prepare(&format!(
+                    "SELECT {column} FROM {table} WHERE {column} IS NOT NULL ORDER BY {column} DESC LIMIT 1",
+                    column = D::UPDATE_TIME_COLUMN,
+                    table = D::TABLE_NAME
+                ))?;
+                let mut rows = stmt.query(params![])?;
+                match rows.next()? {
+                    Some(row) => Ok(Some(row.get::<usize, String>(0)?)),
+                    None => Ok(None),
+                }
+            })
+            .await
+    }
+
+    // 增量同步的进度，保存的是后端返回数据中的 update_time 原文。
+    // 升级前的数据库还没有记录同步进度，使用本地最新数据的时间
+    pub async fn sync_cursor(&self) -> Result<Option<String>, CustomError> {
+        let cursor = self
+            .db
+            .read(|db| {
+                let mut stmt =
+                    db.prepare("SELECT update_time FROM sync_cursor WHERE dataset = ?")?;
+                let mut rows = stmt.query(params![D::NAME])?;
+                match rows.next()? {
+                    Some(row) => Ok(Some(row.get::<usize, String>(0)?)),
+                    None => Ok(None),
+                }
+            })
+            .await?;
+        match cursor {
+            Some(cursor) => Ok(Some(cursor)),
+            None => self.newest_update_time().await,
+        }
+    }
+
+    async fn save_sync_cursor(&self, update_time: String) -> Result<(), CustomError> {
+        self.db
+            .write(move |db| {
+                db.execute(
+                    "INSERT INTO sync_cursor (dataset, update_time) VALUES (?, ?) ON CONFLICT (dataset) DO UPDATE SET update_time = excluded.update_time",
+                    params![D::NAME, update_time],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    pub async fn upsert(&self, pending_data: JsonValue) -> Result<D::Row, CustomError> {
+        let row = D::from_json(&pending_data)?;
+        let validator = self.validator.clone();
+        self.db
+            .write(move |db| {
+                if let Some(validate) = &validator {
+                    validate(db, &row)?;
+                }
+                let mut stmt = db.prepare(&Self::upsert_sql())?;
+                let values = D::to_params(&row);
+                let mut rows = stmt.query(params_from_iter(values.iter()))?;
+                if let Some(row) = rows.next()? {
+                    return Ok(D::from_db_row(row)?);
+                }
+
+                // 不满足 UPSERT_CONDITION 时没有返回行，返回本地已有的数据
+                let conflict_values = D::CONFLICT_COLUMNS.iter().map(|column| {
+                    let index = D::COLUMNS.iter().position(|c| c == column).unwrap();
+                    &values[index]
+                });
+                let condition: Vec<String> = D::CONFLICT_COLUMNS
+                    .iter()
+                    .map(|column| format!("{} = ?", column))
+                    .collect();
+                let mut stmt = db.prepare(&Self::select_sql(&format!(
+                    "WHERE {}",
+                    condition.join(" AND ")
+                )))?;
+                let mut rows = stmt.query(params_from_iter(conflict_values))?;
+                match rows.next()? {
+                    Some(row) => Ok(D::from_db_row(row)?),
+                    None => Err(CustomError::DuckDBError(duckdb::Error::QueryReturnedNoRows)),
+                }
+            })
+            .await
+    }
+
+    pub async fn query_all(&self) -> Result<Vec<D::Row>, CustomError> {
+        self.db
+            .read(|db| {
+                let mut stmt = db.prepare(&Self::select_sql("ORDER BY id"))?;
+                let mut rows = stmt.query(params![])?;
+                let mut data = Vec::new();
+                while let Some(row) = rows.next()? {
+                    data.push(D::from_db_row(row)?);
+                }
+                Ok(data)
+            })
+            .await
+    }
+
+    pub async fn delete_by_id(&self, id: i64) -> Result<usize, CustomError> {
+        self.db
+            .write(move |db| {
+                Ok(db.execute(
+                    &format!("DELETE FROM {} WHERE {} = ?", D::TABLE_NAME, D::COLUMNS[0]),
+                    params![id],
+                )?)
+            })
+            .await
+    }
+
+    // 按某一列的值查询，column 只能是 D::COLUMNS 中的列
+    pub async fn query_by<V>(
+        &self,
+        column: &'static str,
+        value: V,
+    ) -> Result<Vec<D::Row>, CustomError>
+    where
+        V: ToSql + Send + 'static,
+    {
+        debug_assert!(D::COLUMNS.contains(&column));
+        self.db
+            .read(move |db| {
+                let mut stmt = db.prepare(&Self::select_sql(&format!(
+                    "WHERE {} = ? ORDER BY id",
+                    column
+                )))?;
+                let mut rows = stmt.query(params![value])?;
+                let mut data = Vec::new();
+                while let Some(row) = rows.next()? {
+                    data.push(D::from_db_row(row)?);
+                }
+                Ok(data)
+            })
+            .await
+    }
+
+    // 把同步进度交给 fetch_changes，由后端只返回之后有变化的数据
+    pub async fn sync_with<F, Fut>(&self, fetch_changes: F) -> Result<SyncOutcome, CustomError>
+    where
+        F: FnOnce(Option<String>) -> Fut,
+        Fut: Future<Output = Result<JsonValue, CustomError>>,
+    {
+        self.track(|tracker| tracker.fetching(D::NAME));
+        let started = Instant::now();
+        let mut guard = CancelGuard {
+            tracker: self.tracker.as_deref(),
+            dataset: D::NAME,
+            finished: false,
+        };
+        let result = self.sync_inner(fetch_changes).await;
+        guard.finished = true;
+        let duration_ms = started.elapsed().as_millis() as u64;
+        match &result {
+            Ok(outcome) => log::info!(
+                dataset = D::NAME,
+                status = outcome.status,
+                received = outcome.received,
+                applied = outcome.applied,
+                rejected = outcome.rejected.len(),
+                duration_ms = duration_ms;
+                "同步完成"
+            ),
+            Err(err) => log::warn!(
+                dataset = D::NAME,
+                duration_ms = duration_ms;
+                "同步失败: {}", err
+            ),
+        }
+        match &result {
+            Ok(outcome) if outcome.status == 0 => self.track(|tracker| {
+                tracker.done(
+                    D::NAME,
+                    outcome.received,
+                    outcome.applied,
+                    outcome.rejected.len(),
+                )
+            }),
+            Ok(outcome) => self.track(|tracker| {
+                tracker.failed(
+                    D::NAME,
+                    format!("后端返回状态 {}: {}", outcome.status, outcome.message),
+                )
+            }),
+            Err(err) => self.track(|tracker| tracker.failed(D::NAME, err.to_string())),
+        }
+        result
+    }
+
+    async fn sync_inner<F, Fut>(&self, fetch_changes: F) -> Result<SyncOutcome, CustomError>
+    where
+        F: FnOnce(Option<String>) -> Fut,
+        Fut: Future<Output = Result<JsonValue, CustomError>>,
+    {
+        let cursor = self.sync_cursor().await?;
+        match &cursor {
+            Some(update_time) => {
+                log::debug!(dataset = D::NAME; "同步进度: {}", update_time)
+            }
+            None => log::debug!(dataset = D::NAME; "本地无数据"),
+        }
+        let cursor_time = cursor.as_deref().and_then(parse_update_time);
+
+        let mut response_json = fetch_changes(cursor).await?;
+
+        // response_json = {status: 0 | 1 | 2, message: "xxx", content?: []}
+        let status = response_json["status"].as_i64().unwrap_or(-1);
+        let message = response_json["message"].as_str().unwrap_or("").to_string();
+
+        // 如果status不等于0，证明传入token有错或者后端有问题，原样返回后端响应
+        if status != 0 {
+            return Ok(SyncOutcome {
+                status,
+                message,
+                response: response_json.take(),
+                received: 0,
+                applied: 0,
+                rejected: Vec::new(),
+            });
+        }
+
+        // 把最新数据插入到本地数据库，单条插入失败不影响其他数据
+        let content = response_json["content"].take();
+        let items = content.as_array().map(Vec::as_slice).unwrap_or(&[]);
+        log::info!(dataset = D::NAME, received = items.len(); "获取到新数据");
+        self.track(|tracker| tracker.applying(D::NAME, 0, items.len()));
+        let mut applied = 0;
+        let mut rejected = Vec::new();
+        // 已写入数据的更新时间，用于计算下一次同步的进度
+        let mut applied_times: Vec<(DateTime<FixedOffset>, String)> = Vec::new();
+        // 写入失败的数据中最早的更新时间。同步进度不能越过它，下次同步时重新获取这些数据
+        let mut oldest_rejected: Option<DateTime<FixedOffset>> = None;
+        // 写入失败的数据没有可解析的更新时间时无法确定位置，本次不推进同步进度
+        let mut hold_cursor = false;
+        for (index, item) in items.iter().enumerate() {
+            let update_time = item[D::UPDATE_TIME_COLUMN]
+                .as_str()
+                .and_then(|raw| parse_update_time(raw).map(|time| (time, raw.to_string())));
+            match self.upsert(item.to_owned()).await {
+                Ok(_) => {
+                    applied += 1;
+                    applied_times.extend(update_time);
+                }
+                Err(err) => {
+                    log::warn!(dataset = D::NAME; "写入失败: {}", err);
+                    rejected.push(err.to_string());
+                    match update_time {
+                        Some((time, _)) => {
+                            oldest_rejected =
+                                Some(oldest_rejected.map_or(time, |oldest| oldest.min(time)))
+                        }
+                        None => hold_cursor = true,
+                    }
+                }
+            }
+            self.track(|tracker| tracker.applying(D::NAME, index + 1, items.len()));
+        }
+
+        // 同步进度只前进不后退，也不越过写入失败的数据
+        let next_cursor = applied_times
+            .into_iter()
+            .filter(|(time, _)| oldest_rejected.is_none_or(|oldest| *time < oldest))
+            .max_by_key(|(time, _)| *time);
+        if let (false, Some((time, raw))) = (hold_cursor, next_cursor) {
+            if cursor_time.is_none_or(|cursor_time| time > cursor_time) {
+                self.save_sync_cursor(raw).await?;
+            }
+        }
+
+        Ok(SyncOutcome {
+            status,
+            message,
+            response: response_json,
+            received: items.len(),
+            applied,
+            rejected,
+        })
+    }
+}
+
+// 同时支持 RFC 3339 和 DuckDB 输出的 "2024-11-01 08:00:00+00" 格式
+pub fn parse_update_time(value: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%d %H:%M:%S%.f%#z"))
+        .ok()
+}
+
+// 同步任务被取消（future 被丢弃）时，把状态标记为失败，避免一直停留在 fetching / applying
+struct CancelGuard<'a> {
+    tracker: Option<&'a SyncTracker>,
+    dataset: &'static str,
+    finished: bool,
+}
+
+impl Drop for CancelGuard<'_> {
+    fn drop(&mut self) {
+        if let (false, Some(tracker)) = (self.finished, self.tracker) {
+            tracker.failed(self.dataset, "同步已取消".to_string());
+        }
+    }
+}
@@ -0,0 +1,60 @@
+use serde::Serialize;
+use tauri_plugin_http::reqwest;
+use thiserror::Error;
+
+// 案例内容校验失败的位置，path 为 JSON Pointer，例如 /内容/绩效指标/0/分值
+#[derive(Debug, Clone, Serialize)]
+pub struct ValidationIssue {
+    pub path: String,
+    pub message: String,
+}
+
+fn format_issues(issues: &[ValidationIssue]) -> String {
+    issues
+        .iter()
+        .map(|issue| format!("{}: {}", issue.path, issue.message))
+        .collect::<Vec<_>>()
+        .join("; ")
+}
+
+#[derive(Error, Debug)]
+pub enum CustomError {
+    #[error("DuckDB error: {0}")]
+    DuckDBError(#[from] duckdb::Error),
+    #[error("Reqwest error: {0}")]
+    ReqwestError(#[from] reqwest::Error),
+    #[error("Join error: {0}")]
+    JoinError(#[from] tokio::task::JoinError),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Tauri error: {0}")]
+    TauriError(#[from] tauri::Error),
+    #[error("Not found: {0}")]
+    NotFound(String),
+    #[error("Already exists: {0}")]
+    AlreadyExists(String),
+    #[error("Validation error: {}", format_issues(.0))]
+    ValidationError(Vec<ValidationIssue>),
+    #[error("Invalid schema: {0}")]
+    InvalidSchema(String),
+    #[error("Operation cancelled")]
+    Cancelled,
+    #[error("Operation failed unexpectedly")]
+    OperationFailed,
+    #[error("Invalid query: {0}")]
+    InvalidQuery(String),
+    #[error("Query timed out after {0} seconds")]
+    Timeout(u64),
+    #[error("Invalid attachment source: {0}")]
+    InvalidAttachmentSource(String),
+}
+
+// 命令返回的错误需要能序列化给前端
+impl Serialize for CustomError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
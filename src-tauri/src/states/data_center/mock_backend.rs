@@ -0,0 +1,89 @@
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::io::Read;
+use std::net::SocketAddr;
+use std::sync::{Arc, Mutex};
+use std::thread::JoinHandle;
+use tiny_http::{Header, Response, Server};
+
+// 测试用的进程内 HTTP 后端：按路径返回预设的 JSON，并记录收到的请求体。
+// 未设置响应的路径返回 404，用于测试后端异常时的错误处理。
+pub struct MockBackend {
+    server: Arc<Server>,
+    addr: SocketAddr,
+    responses: Arc<Mutex<HashMap<String, JsonValue>>>,
+    requests: Arc<Mutex<Vec<(String, JsonValue)>>>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl MockBackend {
+    pub fn start() -> Self {
+        let server = Arc::new(Server::http("127.0.0.1:0").expect("mock backend bind failed"));
+        let addr = server.server_addr().to_ip().unwrap();
+        let responses: Arc<Mutex<HashMap<String, JsonValue>>> = Arc::default();
+        let requests: Arc<Mutex<Vec<(String, JsonValue)>>> = Arc::default();
+
+        let worker_server = Arc::clone(&server);
+        let worker_responses = Arc::clone(&responses);
+        let worker_requests = Arc::clone(&requests);
+        let worker = std::thread::spawn(move || {
+            for mut request in worker_server.incoming_requests() {
+                let path = request.url().split('?').next().unwrap_or("").to_string();
+                let mut body = String::new();
+                let _ = request.as_reader().read_to_string(&mut body);
+                let body = serde_json::from_str(&body).unwrap_or(JsonValue::Null);
+                worker_requests.lock().unwrap().push((path.clone(), body));
+
+                let response = worker_responses.lock().unwrap().get(&path).cloned();
+                let _ = match response {
+                    Some(response) => {
+                        request.respond(Response::from_string(response.to_string()).with_header(
+                            Header::from_bytes("Content-Type", "application/json").unwrap(),
+                        ))
+                    }
+                    None => request.respond(Response::empty(404)),
+                };
+            }
+        });
+
+        Self {
+            server,
+            addr,
+            responses,
+            requests,
+            worker: Some(worker),
+        }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}/{}", self.addr, path.trim_start_matches('/'))
+    }
+
+    pub fn respond(&self, path: &str, response: JsonValue) {
+        self.responses
+            .lock()
+            .unwrap()
+            .insert(format!("/{}", path.trim_start_matches('/')), response);
+    }
+
+    // 某个路径收到的所有请求体，按接收顺序排列
+    pub fn requests(&self, path: &str) -> Vec<JsonValue> {
+        let path = format!("/{}", path.trim_start_matches('/'));
+        self.requests
+            .lock()
+            .unwrap()
+            .iter()
+            .filter(|(request_path, _)| *request_path == path)
+            .map(|(_, body)| body.clone())
+            .collect()
+    }
+}
+
+impl Drop for MockBackend {
+    fn drop(&mut self) {
+        self.server.unblock();
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
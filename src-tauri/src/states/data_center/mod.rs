@@ -1 +1,10 @@
+pub mod connection;
+pub mod dataset;
+pub mod error;
+#[cfg(test)]
+pub mod mock_backend;
+pub mod operation;
 pub mod performance_evaluation;
+pub mod sql_console;
+pub mod sync_status;
+pub mod tabular;
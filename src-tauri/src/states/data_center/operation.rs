@@ -0,0 +1,402 @@
+use crate::states::data_center::error::CustomError;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::future::Future;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+use tauri::async_runtime::{self, JoinHandle};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct OperationInfo {
+    pub id: String,
+    // 操作类型，例如 query_case_data
+    pub kind: String,
+    // 同一类型中区分不同请求的参数，例如项目类型
+    pub key: String,
+    pub started_at: String,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum OperationEvent {
+    Started,
+    // 相同的操作正在执行，没有重新开始
+    Joined,
+    Cancelled,
+    Finished,
+    // 执行操作的任务没有返回结果就结束了，例如发生 panic
+    Failed,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum OperationPolicy {
+    // 新的请求取消同一类型的旧操作，旧操作的结果不再发送
+    Supersede,
+    // 不同 key 的操作可以同时执行，相同 key 的操作正在执行时拒绝新的请求
+    Exclusive,
+}
+
+// 操作状态变化时的回调，应用中发送 data_center_operation 事件给前端
+pub type OperationReporter = Arc<dyn Fn(OperationEvent, &OperationInfo) + Send + Sync>;
+
+pub enum OperationStart {
+    Started(String),
+    Joined(String),
+}
+
+struct Entry {
+    info: OperationInfo,
+    handle: Option<JoinHandle<()>>,
+    started: Instant,
+}
+
+#[derive(Default)]
+struct Operations {
+    running: HashMap<String, Entry>,
+    // 每种操作最新一次请求的 id
+    latest: HashMap<String, String>,
+}
+
+// 数据中心中耗时的操作（同步、查询、导入、下载附件），每个操作分配一个 id，
+// 可以通过 id 取消，相同的请求不会重复执行，被新请求取代的操作不会再发送结果。
+pub struct OperationRegistry {
+    reporter: OperationReporter,
+    operations: Mutex<Operations>,
+}
+
+impl OperationRegistry {
+    pub fn new(reporter: OperationReporter) -> Self {
+        Self {
+            reporter,
+            operations: Mutex::new(Operations::default()),
+        }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Operations> {
+        self.operations
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn emit(&self, event: OperationEvent, info: &OperationInfo) {
+        (self.reporter)(event, info);
+    }
+
+    pub fn begin(&self, kind: &str, key: &str, policy: OperationPolicy) -> OperationStart {
+        let mut operations = self.lock();
+        let in_flight = operations
+            .running
+            .values()
+            .find(|entry| entry.info.kind == kind && entry.info.key == key)
+            .map(|entry| entry.info.clone());
+        if let Some(info) = in_flight {
+            self.emit(OperationEvent::Joined, &info);
+            return OperationStart::Joined(info.id);
+        }
+
+        if policy == OperationPolicy::Supersede {
+            let superseded: Vec<String> = operations
+                .running
+                .values()
+                .filter(|entry| entry.info.kind == kind)
+                .map(|entry| entry.info.id.clone())
+                .collect();
+            for id in superseded {
+                self.cancel_locked(&mut operations, &id);
+            }
+        }
+
+        let info = OperationInfo {
+            id: uuid::Uuid::new_v4().to_string(),
+            kind: kind.to_string(),
+            key: key.to_string(),
+            started_at: chrono::Local::now().to_rfc3339(),
+        };
+        operations.latest.insert(kind.to_string(), info.id.clone());
+        operations.running.insert(
+            info.id.clone(),
+            Entry {
+                info: info.clone(),
+                handle: None,
+                started: Instant::now(),
+            },
+        );
+        log::debug!(
+            operation_id = info.id.as_str(),
+            kind = kind,
+            key = key;
+            "操作开始"
+        );
+        self.emit(OperationEvent::Started, &info);
+        OperationStart::Started(info.id)
+    }
+
+    // 关联执行操作的任务，取消时中止该任务；任务开始前已被取消时直接中止
+    pub fn attach(&self, id: &str, handle: JoinHandle<()>) {
+        match self.lock().running.get_mut(id) {
+            Some(entry) => entry.handle = Some(handle),
+            None => handle.abort(),
+        }
+    }
+
+    pub fn cancel(&self, id: &str) -> bool {
+        let mut operations = self.lock();
+        self.cancel_locked(&mut operations, id)
+    }
+
+    fn cancel_locked(&self, operations: &mut Operations, id: &str) -> bool {
+        let Some(entry) = operations.running.remove(id) else {
+            return false;
+        };
+        if let Some(handle) = entry.handle {
+            handle.abort();
+        }
+        log::info!(
+            operation_id = entry.info.id.as_str(),
+            kind = entry.info.kind.as_str(),
+            key = entry.info.key.as_str(),
+            duration_ms = entry.started.elapsed().as_millis() as u64;
+            "操作已取消"
+        );
+        if operations.latest.get(&entry.info.kind) == Some(&entry.info.id) {
+            operations.latest.remove(&entry.info.kind);
+        }
+        self.emit(OperationEvent::Cancelled, &entry.info);
+        true
+    }
+
+    // 操作没有正常完成时调用，中止仍在执行的任务。操作已被取消或已完成时返回 false
+    pub fn fail(&self, id: &str) -> bool {
+        let mut operations = self.lock();
+        let Some(entry) = operations.running.remove(id) else {
+            return false;
+        };
+        if let Some(handle) = entry.handle {
+            handle.abort();
+        }
+        log::warn!(
+            operation_id = entry.info.id.as_str(),
+            kind = entry.info.kind.as_str(),
+            key = entry.info.key.as_str(),
+            duration_ms = entry.started.elapsed().as_millis() as u64;
+            "操作异常结束"
+        );
+        if operations.latest.get(&entry.info.kind) == Some(&entry.info.id) {
+            operations.latest.remove(&entry.info.kind);
+        }
+        self.emit(OperationEvent::Failed, &entry.info);
+        true
+    }
+
+    // 在执行操作的任务中持有，通过 OperationGuard::finish 完成操作
+    pub fn guard(&self, id: &str) -> OperationGuard<'_> {
+        OperationGuard {
+            registry: self,
+            id: Some(id.to_string()),
+        }
+    }
+
+    // 操作完成时调用。只有未被取消、也没有被新请求取代时才执行 deliver 发送结果，
+    // deliver 在锁内执行，保证发送期间不会有新的请求开始。
+    pub fn finish(&self, id: &str, deliver: impl FnOnce()) -> bool {
+        let mut operations = self.lock();
+        let Some(entry) = operations.running.remove(id) else {
+            return false;
+        };
+        let current = operations.latest.get(&entry.info.kind) == Some(&entry.info.id);
+        log::info!(
+            operation_id = entry.info.id.as_str(),
+            kind = entry.info.kind.as_str(),
+            key = entry.info.key.as_str(),
+            duration_ms = entry.started.elapsed().as_millis() as u64,
+            delivered = current;
+            "操作完成"
+        );
+        if current {
+            operations.latest.remove(&entry.info.kind);
+            deliver();
+        }
+        self.emit(OperationEvent::Finished, &entry.info);
+        current
+    }
+
+    pub fn list(&self) -> Vec<OperationInfo> {
+        self.lock()
+            .running
+            .values()
+            .map(|entry| entry.info.clone())
+            .collect()
+    }
+
+    // 由命令调用的操作：在独立任务中执行以便取消，取消后返回 CustomError::Cancelled
+    pub async fn run<T, F>(&self, kind: &str, key: &str, operation: F) -> Result<T, CustomError>
+    where
+        T: Send + 'static,
+        F: Future<Output = Result<T, CustomError>> + Send + 'static,
+    {
+        let id = match self.begin(kind, key, OperationPolicy::Exclusive) {
+            OperationStart::Started(id) => id,
+            OperationStart::Joined(_) => {
+                return Err(CustomError::AlreadyExists(format!(
+                    "{} {} 正在执行",
+                    kind, key
+                )))
+            }
+        };
+
+        let (sender, receiver) = tokio::sync::oneshot::channel();
+        let handle = async_runtime::spawn(async move {
+            let _ = sender.send(operation.await);
+        });
+        self.attach(&id, handle);
+
+        // 调用方不再等待时 guard 被丢弃，操作同样会结束
+        let guard = self.guard(&id);
+        match receiver.await {
+            Ok(result) => {
+                guard.finish(|| {});
+                result
+            }
+            // 任务结束时没有发送结果：被取消时记录已经移除，否则是任务发生了 panic
+            Err(_) => {
+                if guard.fail() {
+                    Err(CustomError::OperationFailed)
+                } else {
+                    Err(CustomError::Cancelled)
+                }
+            }
+        }
+    }
+}
+
+// 操作在结束前被丢弃（任务 panic 或 future 被丢弃）时标记为失败，
+// 避免记录一直留在 running 中，使之后相同的请求被当作正在执行而忽略
+pub struct OperationGuard<'a> {
+    registry: &'a OperationRegistry,
+    id: Option<String>,
+}
+
+impl OperationGuard<'_> {
+    pub fn finish(mut self, deliver: impl FnOnce()) -> bool {
+        match self.id.take() {
+            Some(id) => self.registry.finish(&id, deliver),
+            None => false,
+        }
+    }
+
+    pub fn fail(mut self) -> bool {
+        match self.id.take() {
+            Some(id) => self.registry.fail(&id),
+            None => false,
+        }
+    }
+}
+
+impl Drop for OperationGuard<'_> {
+    fn drop(&mut self) {
+        if let Some(id) = self.id.take() {
+            self.registry.fail(&id);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn registry() -> (OperationRegistry, Arc<Mutex<Vec<OperationEvent>>>) {
+        let events = Arc::new(Mutex::new(Vec::new()));
+        let reported = Arc::clone(&events);
+        let registry = OperationRegistry::new(Arc::new(move |event, _: &OperationInfo| {
+            reported.lock().unwrap().push(event);
+        }));
+        (registry, events)
+    }
+
+    fn started(start: OperationStart) -> String {
+        match start {
+            OperationStart::Started(id) => id,
+            OperationStart::Joined(_) => panic!("操作应当重新开始"),
+        }
+    }
+
+    #[test]
+    fn test_superseded_operation_is_not_delivered() {
+        let (registry, events) = registry();
+        let first = started(registry.begin("query", "甲", OperationPolicy::Supersede));
+        let second = started(registry.begin("query", "乙", OperationPolicy::Supersede));
+
+        let mut delivered = false;
+        assert!(!registry.finish(&first, || delivered = true));
+        assert!(!delivered);
+        assert!(registry.finish(&second, || delivered = true));
+        assert!(delivered);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                OperationEvent::Started,
+                OperationEvent::Cancelled,
+                OperationEvent::Started,
+                OperationEvent::Finished
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cancel_before_attach_aborts_task() {
+        let (registry, _) = registry();
+        let id = started(registry.begin("import", "", OperationPolicy::Exclusive));
+        assert!(registry.cancel(&id));
+
+        let (sender, receiver) = tokio::sync::oneshot::channel::<()>();
+        let handle = async_runtime::spawn(async move {
+            tokio::time::sleep(Duration::from_secs(60)).await;
+            let _ = sender.send(());
+        });
+        registry.attach(&id, handle);
+        // 任务被中止后 sender 被丢弃
+        assert!(async_runtime::block_on(receiver).is_err());
+        assert!(registry.list().is_empty());
+    }
+
+    #[test]
+    fn test_panicked_operation_is_failed() {
+        let (registry, events) = registry();
+        async fn panicking_operation() -> Result<(), CustomError> {
+            panic!("导入失败")
+        }
+
+        let result = async_runtime::block_on(registry.run("import", "", panicking_operation()));
+        assert!(matches!(result, Err(CustomError::OperationFailed)));
+        assert!(registry.list().is_empty());
+
+        let id = started(registry.begin("query", "甲", OperationPolicy::Supersede));
+        drop(registry.guard(&id));
+        assert!(registry.list().is_empty());
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![
+                OperationEvent::Started,
+                OperationEvent::Failed,
+                OperationEvent::Started,
+                OperationEvent::Failed
+            ]
+        );
+    }
+
+    #[test]
+    fn test_same_request_is_joined() {
+        let (registry, events) = registry();
+        let id = started(registry.begin("query", "甲", OperationPolicy::Supersede));
+        match registry.begin("query", "甲", OperationPolicy::Supersede) {
+            OperationStart::Joined(joined) => assert_eq!(joined, id),
+            OperationStart::Started(_) => panic!("相同的请求不应重复执行"),
+        }
+        assert_eq!(registry.list().len(), 1);
+        assert_eq!(
+            *events.lock().unwrap(),
+            vec![OperationEvent::Started, OperationEvent::Joined]
+        );
+    }
+}
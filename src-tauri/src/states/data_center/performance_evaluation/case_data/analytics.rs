@@ -0,0 +1,280 @@
+use crate::states::data_center::error::{CustomError, ValidationIssue};
+use crate::states::data_center::tabular::{query_tabular, TabularResult};
+use duckdb::types::Value;
+use duckdb::Connection;
+use serde::Serialize;
+use serde_json::{Map, Value as JsonValue};
+
+// 报表结果的行数上限，防止前端一次渲染过多数据
+const MAX_REPORT_ROWS: usize = 10_000;
+
+#[derive(Debug, Clone, Copy, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ParameterKind {
+    // 可选的文本，未传入时为 NULL
+    Text,
+    Number {
+        min: f64,
+        max: f64,
+        default: f64,
+    },
+    Choice {
+        options: &'static [&'static str],
+        default: &'static str,
+    },
+}
+
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ReportParameter {
+    pub name: &'static str,
+    pub description: &'static str,
+    pub kind: ParameterKind,
+}
+
+// 报表定义。SQL 只能使用这里列出的报表，参数按定义顺序绑定到 $1、$2 ...
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct ReportDefinition {
+    pub id: &'static str,
+    pub title: &'static str,
+    pub description: &'static str,
+    pub parameters: &'static [ReportParameter],
+    #[serde(skip)]
+    sql: &'static str,
+}
+
+const PROJECT_TYPE_PARAMETER: ReportParameter = ReportParameter {
+    name: "project_type",
+    description: "项目类型，为空时统计全部案例",
+    kind: ParameterKind::Text,
+};
+
+pub const REPORTS: &[ReportDefinition] = &[
+    ReportDefinition {
+        id: "case_count_by_project_type",
+        title: "各项目类型案例数",
+        description: "按项目类型统计案例数量和最近更新时间",
+        parameters: &[],
+        sql: "
+            SELECT 项目类型, count(*) AS 案例数, max(update_time)::VARCHAR AS 最近更新时间
+            FROM 预算绩效管理案例库
+            GROUP BY 项目类型
+            ORDER BY 案例数 DESC, 项目类型
+        ",
+    },
+    ReportDefinition {
+        id: "indicator_score_distribution",
+        title: "绩效指标分值分布",
+        description: "按分值区间统计 内容.绩效指标 中的指标数量",
+        parameters: &[
+            PROJECT_TYPE_PARAMETER,
+            ReportParameter {
+                name: "bucket_size",
+                description: "分值区间宽度",
+                kind: ParameterKind::Number {
+                    min: 1.0,
+                    max: 100.0,
+                    default: 10.0,
+                },
+            },
+        ],
+        sql: "
+            WITH 指标 AS (
+                SELECT unnest(from_json(json_extract(内容, '$.绩效指标'), '[\"JSON\"]')) AS 指标
+                FROM 预算绩效管理案例库
+                WHERE $1::VARCHAR IS NULL OR 项目类型 = $1::VARCHAR
+            ),
+            分值 AS (
+                SELECT TRY_CAST(json_extract_string(指标, '$.分值') AS DOUBLE) AS 分值 FROM 指标
+            )
+            SELECT
+                floor(分值 / $2::DOUBLE) * $2::DOUBLE AS 区间下限,
+                floor(分值 / $2::DOUBLE) * $2::DOUBLE + $2::DOUBLE AS 区间上限,
+                count(*) AS 指标数
+            FROM 分值
+            WHERE 分值 IS NOT NULL
+            GROUP BY 区间下限, 区间上限
+            ORDER BY 区间下限
+        ",
+    },
+    ReportDefinition {
+        id: "indicator_score_by_name",
+        title: "各绩效指标分值统计",
+        description: "按指标名称统计出现次数和分值的平均值、最小值、最大值",
+        parameters: &[PROJECT_TYPE_PARAMETER],
+        sql: "
+            WITH 指标 AS (
+                SELECT unnest(from_json(json_extract(内容, '$.绩效指标'), '[\"JSON\"]')) AS 指标
+                FROM 预算绩效管理案例库
+                WHERE $1::VARCHAR IS NULL OR 项目类型 = $1::VARCHAR
+            )
+            SELECT
+                json_extract_string(指标, '$.名称') AS 指标名称,
+                count(*) AS 出现次数,
+                avg(TRY_CAST(json_extract_string(指标, '$.分值') AS DOUBLE)) AS 平均分值,
+                min(TRY_CAST(json_extract_string(指标, '$.分值') AS DOUBLE)) AS 最低分值,
+                max(TRY_CAST(json_extract_string(指标, '$.分值') AS DOUBLE)) AS 最高分值
+            FROM 指标
+            GROUP BY 指标名称
+            ORDER BY 出现次数 DESC, 指标名称
+        ",
+    },
+    ReportDefinition {
+        id: "case_update_trend",
+        title: "案例更新趋势",
+        description: "按周期统计更新的案例数量",
+        parameters: &[
+            PROJECT_TYPE_PARAMETER,
+            ReportParameter {
+                name: "granularity",
+                description: "统计周期",
+                kind: ParameterKind::Choice {
+                    options: &["day", "week", "month", "quarter", "year"],
+                    default: "month",
+                },
+            },
+        ],
+        sql: "
+            SELECT
+                date_trunc($2::VARCHAR, update_time::TIMESTAMP)::DATE::VARCHAR AS 周期,
+                count(*) AS 案例数
+            FROM 预算绩效管理案例库
+            WHERE update_time IS NOT NULL AND ($1::VARCHAR IS NULL OR 项目类型 = $1::VARCHAR)
+            GROUP BY 周期
+            ORDER BY 周期
+        ",
+    },
+];
+
+pub fn report(report_id: &str) -> Result<&'static ReportDefinition, CustomError> {
+    REPORTS
+        .iter()
+        .find(|report| report.id == report_id)
+        .ok_or_else(|| CustomError::NotFound(format!("报表 {}", report_id)))
+}
+
+// 按报表定义校验前端传入的参数，未传入的参数使用默认值
+fn bind_parameters(
+    report: &ReportDefinition,
+    arguments: &Map<String, JsonValue>,
+) -> Result<Vec<Value>, CustomError> {
+    let mut issues: Vec<ValidationIssue> = arguments
+        .keys()
+        .filter(|name| !report.parameters.iter().any(|p| p.name == name.as_str()))
+        .map(|name| ValidationIssue {
+            path: format!("/{}", name),
+            message: "未知的参数".to_string(),
+        })
+        .collect();
+
+    let mut values = Vec::with_capacity(report.parameters.len());
+    for parameter in report.parameters {
+        let argument = arguments
+            .get(parameter.name)
+            .filter(|value| !value.is_null());
+        let value = match (parameter.kind, argument) {
+            (ParameterKind::Text, None) => Ok(Value::Null),
+            (ParameterKind::Text, Some(JsonValue::String(text))) => Ok(Value::Text(text.clone())),
+            (ParameterKind::Text, Some(_)) => Err("必须是字符串".to_string()),
+            (ParameterKind::Number { default, .. }, None) => Ok(Value::Double(default)),
+            (ParameterKind::Number { min, max, .. }, Some(argument)) => match argument.as_f64() {
+                Some(number) if (min..=max).contains(&number) => Ok(Value::Double(number)),
+                _ => Err(format!("必须是 {} 到 {} 之间的数字", min, max)),
+            },
+            (ParameterKind::Choice { default, .. }, None) => Ok(Value::Text(default.to_string())),
+            (ParameterKind::Choice { options, .. }, Some(argument)) => match argument.as_str() {
+                Some(choice) if options.contains(&choice) => Ok(Value::Text(choice.to_string())),
+                _ => Err(format!("必须是 {} 之一", options.join("、"))),
+            },
+        };
+        match value {
+            Ok(value) => values.push(value),
+            Err(message) => issues.push(ValidationIssue {
+                path: format!("/{}", parameter.name),
+                message,
+            }),
+        }
+    }
+
+    if issues.is_empty() {
+        Ok(values)
+    } else {
+        Err(CustomError::ValidationError(issues))
+    }
+}
+
+pub fn run_report(
+    db: &Connection,
+    report: &ReportDefinition,
+    arguments: &Map<String, JsonValue>,
+) -> Result<TabularResult, CustomError> {
+    let parameters = bind_parameters(report, arguments)?;
+    query_tabular(db, report.sql, parameters, MAX_REPORT_ROWS)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::migrations::open_test_database;
+    use super::*;
+    use serde_json::json;
+
+    const PROJECT_TYPE: &str = "部门整体支出绩效评价";
+
+    #[test]
+    fn test_reports() {
+        let db = open_test_database();
+        db.execute_batch(&format!(
+            r#"INSERT INTO 预算绩效管理案例库 (项目名称, 项目类型, 内容, update_time) VALUES
+                ('项目一', '{0}', '{{"绩效指标": [{{"名称": "产出指标", "分值": 20}}]}}', '2024-11-01 08:00:00+00'),
+                ('项目二', '{0}', '{{"绩效指标": [{{"名称": "产出指标", "分值": 20}}]}}', '2024-12-01 08:00:00+00'),
+                ('项目三', '政策绩效评价', '{{"绩效指标": [{{"名称": "产出指标", "分值": 20}}]}}', '2024-12-02 08:00:00+00');"#,
+            PROJECT_TYPE
+        ))
+        .unwrap();
+
+        let counts = run_report(
+            &db,
+            report("case_count_by_project_type").unwrap(),
+            &Map::new(),
+        )
+        .unwrap();
+        assert_eq!(counts.columns[0].name, "项目类型");
+        assert_eq!(counts.rows[0][0], PROJECT_TYPE);
+        assert_eq!(counts.rows[0][1], 2);
+
+        let arguments = json!({"project_type": PROJECT_TYPE, "bucket_size": 15});
+        let distribution = run_report(
+            &db,
+            report("indicator_score_distribution").unwrap(),
+            arguments.as_object().unwrap(),
+        )
+        .unwrap();
+        assert_eq!(
+            distribution.rows,
+            vec![vec![json!(15.0), json!(30.0), json!(2)]]
+        );
+
+        let trend = run_report(&db, report("case_update_trend").unwrap(), &Map::new()).unwrap();
+        assert_eq!(trend.rows.len(), 2);
+
+        assert!(matches!(
+            report("drop_table"),
+            Err(CustomError::NotFound(_))
+        ));
+    }
+
+    #[test]
+    fn test_bind_parameters() {
+        let report = report("case_update_trend").unwrap();
+        let values = bind_parameters(report, &Map::new()).unwrap();
+        assert_eq!(values, vec![Value::Null, Value::Text("month".to_string())]);
+
+        let arguments = json!({"granularity": "hour", "limit": 1});
+        match bind_parameters(report, arguments.as_object().unwrap()) {
+            Err(CustomError::ValidationError(issues)) => {
+                let paths: Vec<&str> = issues.iter().map(|issue| issue.path.as_str()).collect();
+                assert_eq!(paths, vec!["/limit", "/granularity"]);
+            }
+            other => panic!("unexpected result: {:?}", other),
+        }
+    }
+}
@@ -0,0 +1,443 @@
+use super::dataset::CaseRecord;
+use crate::states::data_center::connection::ConnectionManager;
+use crate::states::data_center::error::CustomError;
+use duckdb::{params, Connection};
+use serde::Serialize;
+use sha2::{Digest, Sha256};
+use std::collections::HashSet;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime};
+use tauri_plugin_http::reqwest;
+use tokio::task;
+
+// 下载完成到写入附件记录之间文件还没有被引用，清理时跳过最近修改过的文件，避免误删正在保存的附件
+const GARBAGE_GRACE_PERIOD: Duration = Duration::from_secs(60 * 60);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AttachmentStatus {
+    // 案例没有附件
+    None,
+    // 尚未下载，或文件路径已变化需要重新下载
+    Pending,
+    Downloading,
+    Downloaded,
+    Failed,
+}
+
+impl AttachmentStatus {
+    fn as_str(self) -> &'static str {
+        match self {
+            AttachmentStatus::None => "none",
+            AttachmentStatus::Pending => "pending",
+            AttachmentStatus::Downloading => "downloading",
+            AttachmentStatus::Downloaded => "downloaded",
+            AttachmentStatus::Failed => "failed",
+        }
+    }
+
+    fn parse(value: &str) -> Self {
+        match value {
+            "downloading" => AttachmentStatus::Downloading,
+            "downloaded" => AttachmentStatus::Downloaded,
+            "failed" => AttachmentStatus::Failed,
+            _ => AttachmentStatus::Pending,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseAttachment {
+    pub case_id: i64,
+    // 案例中的 文件路径，只支持 http(s) 地址
+    pub source: String,
+    pub status: AttachmentStatus,
+    pub sha256: Option<String>,
+    pub size: Option<i64>,
+    pub error: Option<String>,
+}
+
+// 案例附件存储：文件按内容的 SHA-256 存放在应用数据目录中，相同内容只保存一份，
+// 下载状态按案例记录在 预算绩效管理案例附件 表中。
+pub struct AttachmentStore {
+    db: Arc<ConnectionManager>,
+    dir: PathBuf,
+    // 正在下载的案例。下载任务被取消或应用退出时，记录会停留在 downloading
+    downloading: Mutex<HashSet<i64>>,
+}
+
+// 下载结束、出错或任务被取消时都从 downloading 中移除
+struct DownloadGuard<'a> {
+    downloading: &'a Mutex<HashSet<i64>>,
+    case_id: i64,
+}
+
+impl Drop for DownloadGuard<'_> {
+    fn drop(&mut self) {
+        self.downloading.lock().unwrap().remove(&self.case_id);
+    }
+}
+
+impl AttachmentStore {
+    pub fn new(db: Arc<ConnectionManager>, dir: PathBuf) -> Self {
+        Self {
+            db,
+            dir,
+            downloading: Mutex::new(HashSet::new()),
+        }
+    }
+
+    fn stored_path(&self, stored_name: &str) -> PathBuf {
+        self.dir.join(stored_name)
+    }
+
+    pub async fn status(&self, case: &CaseRecord) -> Result<CaseAttachment, CustomError> {
+        let case_id = case_id(case)?;
+        let source = case.文件路径.clone();
+        if source.is_empty() {
+            return Ok(CaseAttachment {
+                case_id,
+                source,
+                status: AttachmentStatus::None,
+                sha256: None,
+                size: None,
+                error: None,
+            });
+        }
+
+        let row = self.db.read(move |db| load_row(db, case_id)).await?;
+        Ok(match row {
+            // 没有正在进行的下载时，停留在 downloading 的记录视为可以重新下载
+            Some(row)
+                if row.attachment.source == source
+                    && row.attachment.status == AttachmentStatus::Downloading
+                    && !self.downloading.lock().unwrap().contains(&case_id) =>
+            {
+                CaseAttachment {
+                    status: AttachmentStatus::Pending,
+                    ..row.attachment
+                }
+            }
+            // 文件路径变化后之前下载的文件不再对应该案例
+            Some(row) if row.attachment.source == source => row.attachment,
+            _ => CaseAttachment {
+                case_id,
+                source,
+                status: AttachmentStatus::Pending,
+                sha256: None,
+                size: None,
+                error: None,
+            },
+        })
+    }
+
+    pub async fn download(&self, case: &CaseRecord) -> Result<CaseAttachment, CustomError> {
+        let case_id = case_id(case)?;
+        let source = case.文件路径.clone();
+        if source.is_empty() {
+            return Err(CustomError::NotFound(format!(
+                "案例 {} 没有附件",
+                case.项目名称
+            )));
+        }
+
+        self.downloading.lock().unwrap().insert(case_id);
+        let _guard = DownloadGuard {
+            downloading: &self.downloading,
+            case_id,
+        };
+        self.set_status(case_id, &source, AttachmentStatus::Downloading, None)
+            .await?;
+        match self.fetch_and_store(&source).await {
+            Ok((sha256, stored_name, size)) => {
+                let attachment = CaseAttachment {
+                    case_id,
+                    source: source.clone(),
+                    status: AttachmentStatus::Downloaded,
+                    sha256: Some(sha256),
+                    size: Some(size),
+                    error: None,
+                };
+                let row = attachment.clone();
+                self.db
+                    .write(move |db| {
+                        db.execute(
+                            "INSERT OR REPLACE INTO 预算绩效管理案例附件 (case_id, source, sha256, stored_name, size, status, error, update_time) VALUES (?, ?, ?, ?, ?, ?, NULL, current_timestamp)",
+                            params![
+                                row.case_id,
+                                row.source,
+                                row.sha256,
+                                stored_name,
+                                row.size,
+                                row.status.as_str()
+                            ],
+                        )?;
+                        Ok(())
+                    })
+                    .await?;
+                Ok(attachment)
+            }
+            Err(err) => {
+                log::warn!("预算绩效管理案例附件 - 下载失败: {}, 错误: {}", source, err);
+                self.set_status(
+                    case_id,
+                    &source,
+                    AttachmentStatus::Failed,
+                    Some(err.to_string()),
+                )
+                .await?;
+                Err(err)
+            }
+        }
+    }
+
+    // 返回已下载附件的本地路径，打开前重新计算哈希，文件被修改或损坏时标记为失败
+    pub async fn verified_path(&self, case: &CaseRecord) -> Result<PathBuf, CustomError> {
+        let case_id = case_id(case)?;
+        let row = self
+            .db
+            .read(move |db| load_row(db, case_id))
+            .await?
+            .filter(|row| {
+                row.attachment.source == case.文件路径
+                    && row.attachment.status == AttachmentStatus::Downloaded
+            })
+            .ok_or_else(|| {
+                CustomError::NotFound(format!("案例 {} 的附件尚未下载", case.项目名称))
+            })?;
+
+        let path = self.stored_path(&row.stored_name);
+        let expected = row.attachment.sha256.clone().unwrap_or_default();
+        let hash_path = path.clone();
+        let actual = task::spawn_blocking(move || -> Result<String, CustomError> {
+            Ok(sha256_hex(&std::fs::read(hash_path)?))
+        })
+        .await?;
+
+        match actual {
+            Ok(actual) if actual == expected => Ok(path),
+            Ok(_) => {
+                let message = "附件文件校验失败，请重新下载".to_string();
+                self.set_status(
+                    case_id,
+                    &case.文件路径,
+                    AttachmentStatus::Failed,
+                    Some(message.clone()),
+                )
+                .await?;
+                Err(CustomError::NotFound(message))
+            }
+            Err(err) => {
+                self.set_status(
+                    case_id,
+                    &case.文件路径,
+                    AttachmentStatus::Failed,
+                    Some(err.to_string()),
+                )
+                .await?;
+                Err(err)
+            }
+        }
+    }
+
+    pub async fn remove(&self, case_id: i64) -> Result<(), CustomError> {
+        self.db
+            .write(move |db| {
+                db.execute(
+                    "DELETE FROM 预算绩效管理案例附件 WHERE case_id = ?",
+                    params![case_id],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    // 删除已不存在的案例的附件记录，以及没有任何记录引用的文件，返回删除的文件数
+    pub async fn collect_garbage(&self) -> Result<usize, CustomError> {
+        let referenced = self
+            .db
+            .write(|db| {
+                db.execute_batch(
+                    "DELETE FROM 预算绩效管理案例附件 WHERE case_id NOT IN (SELECT id FROM 预算绩效管理案例库);",
+                )?;
+                let mut stmt = db.prepare(
+                    "SELECT DISTINCT stored_name FROM 预算绩效管理案例附件 WHERE stored_name IS NOT NULL",
+                )?;
+                let mut rows = stmt.query(params![])?;
+                let mut referenced = HashSet::new();
+                while let Some(row) = rows.next()? {
+                    referenced.insert(row.get::<usize, String>(0)?);
+                }
+                Ok(referenced)
+            })
+            .await?;
+
+        let dir = self.dir.clone();
+        task::spawn_blocking(move || -> Result<usize, CustomError> {
+            if !dir.exists() {
+                return Ok(0);
+            }
+            let mut removed = 0;
+            for entry in std::fs::read_dir(&dir)? {
+                let entry = entry?;
+                let name = entry.file_name().to_string_lossy().to_string();
+                // 正在写入的 .part 临时文件修改时间一直在更新，会被下面的宽限期跳过；
+                // 下载中断留下的临时文件超过宽限期后一并清理
+                if !entry.file_type()?.is_file() || referenced.contains(&name) {
+                    continue;
+                }
+                let recently_modified = entry
+                    .metadata()?
+                    .modified()
+                    .ok()
+                    .and_then(|modified| SystemTime::now().duration_since(modified).ok())
+                    .is_none_or(|age| age < GARBAGE_GRACE_PERIOD);
+                if recently_modified {
+                    continue;
+                }
+                std::fs::remove_file(entry.path())?;
+                removed += 1;
+            }
+            log::info!("预算绩效管理案例附件 - 清理未引用的文件个数: {}", removed);
+            Ok(removed)
+        })
+        .await?
+    }
+
+    async fn set_status(
+        &self,
+        case_id: i64,
+        source: &str,
+        status: AttachmentStatus,
+        error: Option<String>,
+    ) -> Result<(), CustomError> {
+        let source = source.to_string();
+        self.db
+            .write(move |db| {
+                db.execute(
+                    "INSERT INTO 预算绩效管理案例附件 (case_id, source, status, error, update_time) VALUES (?, ?, ?, ?, current_timestamp)
+                     ON CONFLICT (case_id) DO UPDATE SET source = excluded.source, status = excluded.status, error = excluded.error, update_time = excluded.update_time",
+                    params![case_id, source, status.as_str(), error],
+                )?;
+                Ok(())
+            })
+            .await
+    }
+
+    // 下载附件内容并按哈希保存，已存在相同内容的文件时直接复用。
+    // 文件路径来自同步或导入的案例数据，不读取本地文件，避免把任意本地文件复制到附件目录中打开。
+    async fn fetch_and_store(&self, source: &str) -> Result<(String, String, i64), CustomError> {
+        let url = reqwest::Url::parse(source)
+            .ok()
+            .filter(|url| matches!(url.scheme(), "http" | "https"))
+            .ok_or_else(|| {
+                CustomError::InvalidAttachmentSource(format!("附件只支持 http(s) 地址: {}", source))
+            })?;
+        let mut response = reqwest::get(url).await?.error_for_status()?;
+
+        // 附件可能很大，边下载边写入临时文件并计算哈希，不把整个文件读入内存。
+        // 临时文件名随机，同时下载相同内容的附件时互不影响
+        let dir = self.dir.clone();
+        let tmp_path = dir.join(format!("{}.part", uuid::Uuid::new_v4()));
+        let (sender, mut receiver) = tokio::sync::mpsc::channel::<Vec<u8>>(8);
+        let writer_dir = dir.clone();
+        let writer_path = tmp_path.clone();
+        let writer = task::spawn_blocking(move || -> Result<(String, i64), CustomError> {
+            std::fs::create_dir_all(&writer_dir)?;
+            let mut file = std::fs::File::create(&writer_path)?;
+            let mut hasher = Sha256::new();
+            let mut size = 0;
+            while let Some(chunk) = receiver.blocking_recv() {
+                hasher.update(&chunk);
+                file.write_all(&chunk)?;
+                size += chunk.len() as i64;
+            }
+            file.sync_all()?;
+            Ok((format!("{:x}", hasher.finalize()), size))
+        });
+        let downloaded = async {
+            while let Some(chunk) = response.chunk().await? {
+                // 写入任务已经出错退出，错误从 writer 返回
+                if sender.send(chunk.to_vec()).await.is_err() {
+                    break;
+                }
+            }
+            Ok::<(), CustomError>(())
+        }
+        .await;
+        drop(sender);
+        let written = writer.await?;
+
+        let extension = extension_of(source);
+        task::spawn_blocking(move || -> Result<(String, String, i64), CustomError> {
+            let (sha256, size) = match downloaded.and(written) {
+                Ok(written) => written,
+                Err(err) => {
+                    let _ = std::fs::remove_file(&tmp_path);
+                    return Err(err);
+                }
+            };
+            let stored_name = match extension {
+                Some(extension) => format!("{}.{}", sha256, extension),
+                None => sha256.clone(),
+            };
+            // 写完临时文件再重命名，中断时不会留下不完整的附件
+            let path = dir.join(&stored_name);
+            if path.exists() {
+                std::fs::remove_file(&tmp_path)?;
+            } else {
+                std::fs::rename(&tmp_path, &path)?;
+            }
+            Ok((sha256, stored_name, size))
+        })
+        .await?
+    }
+}
+
+struct AttachmentRow {
+    attachment: CaseAttachment,
+    stored_name: String,
+}
+
+fn load_row(db: &Connection, case_id: i64) -> Result<Option<AttachmentRow>, CustomError> {
+    let mut stmt = db.prepare(
+        "SELECT case_id, source, status, sha256, size, error, stored_name FROM 预算绩效管理案例附件 WHERE case_id = ?",
+    )?;
+    let mut rows = stmt.query(params![case_id])?;
+    match rows.next()? {
+        Some(row) => Ok(Some(AttachmentRow {
+            attachment: CaseAttachment {
+                case_id: row.get(0)?,
+                source: row.get(1)?,
+                status: AttachmentStatus::parse(&row.get::<usize, String>(2)?),
+                sha256: row.get(3)?,
+                size: row.get(4)?,
+                error: row.get(5)?,
+            },
+            stored_name: row.get::<usize, Option<String>>(6)?.unwrap_or_default(),
+        })),
+        None => Ok(None),
+    }
+}
+
+fn case_id(case: &CaseRecord) -> Result<i64, CustomError> {
+    case.id
+        .ok_or_else(|| CustomError::NotFound(format!("案例 {} 尚未保存", case.项目名称)))
+}
+
+fn sha256_hex(bytes: &[u8]) -> String {
+    format!("{:x}", Sha256::digest(bytes))
+}
+
+// 保留扩展名，打开附件时系统才能选择对应的程序
+fn extension_of(source: &str) -> Option<String> {
+    let path = source.split(['?', '#']).next().unwrap_or(source);
+    Path::new(path)
+        .extension()
+        .map(|extension| extension.to_string_lossy().to_lowercase())
+        .filter(|extension| {
+            !extension.is_empty() && extension.chars().all(|c| c.is_ascii_alphanumeric())
+        })
+}
@@ -0,0 +1,254 @@
+use super::dataset::{CaseRecord, PerformanceEvaluationCaseDataset};
+use crate::states::data_center::dataset::{parse_update_time, Dataset};
+use crate::states::data_center::error::CustomError;
+use crate::states::data_center::performance_evaluation::case_template::dataset::PerformanceEvaluationCaseTemplateDataset;
+use async_trait::async_trait;
+use chrono::{DateTime, FixedOffset};
+use serde_json::{json, Value as JsonValue};
+use std::path::{Path, PathBuf};
+use tauri_plugin_http::reqwest;
+use tokio::task;
+
+// 案例库后端。各方法的响应格式与后端接口一致：{status: 0 | 1 | 2, message: "xxx", content?: []}，
+// status 不为 0 表示 token 有误或后端异常。
+#[async_trait]
+pub trait CaseDataBackend: Send + Sync {
+    // since 为本地最新数据的更新时间，为空时获取全部数据
+    async fn fetch_case_changes(
+        &self,
+        token: &str,
+        since: Option<&str>,
+    ) -> Result<JsonValue, CustomError>;
+
+    async fn fetch_template_changes(
+        &self,
+        token: &str,
+        since: Option<&str>,
+    ) -> Result<JsonValue, CustomError>;
+
+    // 提交本地编辑过的案例
+    async fn push_case_edits(
+        &self,
+        token: &str,
+        cases: &[CaseRecord],
+    ) -> Result<JsonValue, CustomError>;
+}
+
+// 案例库后端接口地址，测试时指向本地的模拟后端
+#[derive(Debug, Clone)]
+pub struct BackendConfig {
+    pub case_data_endpoint: String,
+    pub case_template_endpoint: String,
+    // 后端尚未提供提交接口，未配置时提交会直接报错，不会把案例发到其他地址
+    pub case_push_endpoint: Option<String>,
+}
+
+impl Default for BackendConfig {
+    fn default() -> Self {
+        Self {
+            case_data_endpoint: PerformanceEvaluationCaseDataset::SYNC_ENDPOINT.to_string(),
+            case_template_endpoint: PerformanceEvaluationCaseTemplateDataset::SYNC_ENDPOINT
+                .to_string(),
+            case_push_endpoint: None,
+        }
+    }
+}
+
+pub struct HttpBackend {
+    client: reqwest::Client,
+    config: BackendConfig,
+}
+
+impl HttpBackend {
+    pub fn new(config: BackendConfig) -> Self {
+        Self {
+            client: reqwest::Client::new(),
+            config,
+        }
+    }
+
+    async fn post(&self, endpoint: &str, body: JsonValue) -> Result<JsonValue, CustomError> {
+        let response = self.client.post(endpoint).json(&body).send().await?;
+        Ok(response.json().await?)
+    }
+}
+
+#[async_trait]
+impl CaseDataBackend for HttpBackend {
+    async fn fetch_case_changes(
+        &self,
+        token: &str,
+        since: Option<&str>,
+    ) -> Result<JsonValue, CustomError> {
+        self.post(
+            &self.config.case_data_endpoint,
+            json!({
+                "token": token,
+                "last_update_time": since.unwrap_or("1970-01-01 00:00:00")
+            }),
+        )
+        .await
+    }
+
+    async fn fetch_template_changes(
+        &self,
+        token: &str,
+        since: Option<&str>,
+    ) -> Result<JsonValue, CustomError> {
+        self.post(
+            &self.config.case_template_endpoint,
+            json!({
+                "token": token,
+                "last_update_time": since.unwrap_or("1970-01-01 00:00:00")
+            }),
+        )
+        .await
+    }
+
+    async fn push_case_edits(
+        &self,
+        token: &str,
+        cases: &[CaseRecord],
+    ) -> Result<JsonValue, CustomError> {
+        let endpoint = self
+            .config
+            .case_push_endpoint
+            .as_deref()
+            .ok_or_else(|| CustomError::NotFound("案例提交接口未配置".to_string()))?;
+        self.post(
+            endpoint,
+            json!({
+                "token": token,
+                "content": cases
+            }),
+        )
+        .await
+    }
+}
+
+pub const CASE_DATA_EXPORT_FILE_NAME: &str = "case_data.json";
+pub const CASE_TEMPLATE_EXPORT_FILE_NAME: &str = "case_template.json";
+const OUTBOX_DIR_NAME: &str = "outbox";
+
+// 从导出目录（例如 U 盘）读取后端导出的 JSON 文件，用于无法联网的单位。
+// 导出文件可以是后端接口的完整响应，也可以直接是数据数组；本地编辑写入 outbox 目录，再带回联网环境提交。
+pub struct FileBackend {
+    dir: PathBuf,
+}
+
+impl FileBackend {
+    pub fn new(dir: PathBuf) -> Self {
+        Self { dir }
+    }
+
+    // 目录中没有任何导出文件时通常是选错了目录，导入时直接报错
+    pub fn ensure_export_files(&self) -> Result<(), CustomError> {
+        let found = [CASE_DATA_EXPORT_FILE_NAME, CASE_TEMPLATE_EXPORT_FILE_NAME]
+            .iter()
+            .any(|file_name| self.dir.join(file_name).is_file());
+        if found {
+            Ok(())
+        } else {
+            Err(CustomError::NotFound(format!(
+                "{} 中的导出文件 {} 或 {}",
+                self.dir.display(),
+                CASE_DATA_EXPORT_FILE_NAME,
+                CASE_TEMPLATE_EXPORT_FILE_NAME
+            )))
+        }
+    }
+
+    async fn read_export(
+        &self,
+        file_name: &'static str,
+        since: Option<&str>,
+    ) -> Result<JsonValue, CustomError> {
+        let path = self.dir.join(file_name);
+        let since = since.and_then(parse_update_time);
+        task::spawn_blocking(move || read_export_file(&path, since)).await?
+    }
+}
+
+#[async_trait]
+impl CaseDataBackend for FileBackend {
+    async fn fetch_case_changes(
+        &self,
+        _token: &str,
+        since: Option<&str>,
+    ) -> Result<JsonValue, CustomError> {
+        self.read_export(CASE_DATA_EXPORT_FILE_NAME, since).await
+    }
+
+    async fn fetch_template_changes(
+        &self,
+        _token: &str,
+        since: Option<&str>,
+    ) -> Result<JsonValue, CustomError> {
+        self.read_export(CASE_TEMPLATE_EXPORT_FILE_NAME, since)
+            .await
+    }
+
+    async fn push_case_edits(
+        &self,
+        _token: &str,
+        cases: &[CaseRecord],
+    ) -> Result<JsonValue, CustomError> {
+        let outbox = self.dir.join(OUTBOX_DIR_NAME);
+        let path = outbox.join(format!(
+            "case_edits-{}.json",
+            chrono::Utc::now().format("%Y%m%d%H%M%S")
+        ));
+        let content = serde_json::to_vec_pretty(&json!({ "content": cases }))
+            .map_err(std::io::Error::from)?;
+        let message = format!("已写入 {}", path.display());
+        task::spawn_blocking(move || -> Result<(), CustomError> {
+            std::fs::create_dir_all(&outbox)?;
+            std::fs::write(&path, content)?;
+            Ok(())
+        })
+        .await??;
+        Ok(json!({"status": 0, "message": message}))
+    }
+}
+
+fn read_export_file(
+    path: &Path,
+    since: Option<DateTime<FixedOffset>>,
+) -> Result<JsonValue, CustomError> {
+    // 只导出了案例或只导出了模板时，缺少的一项视为没有变化
+    if !path.exists() {
+        return Ok(json!({"status": 0, "message": "导出文件不存在", "content": []}));
+    }
+    let export: JsonValue =
+        serde_json::from_slice(&std::fs::read(path)?).map_err(std::io::Error::from)?;
+    let (status, message, content) = match export {
+        JsonValue::Array(content) => (0, "从导出文件读取".to_string(), content),
+        mut export => (
+            export["status"].as_i64().unwrap_or(0),
+            export["message"]
+                .as_str()
+                .unwrap_or("从导出文件读取")
+                .to_string(),
+            export["content"]
+                .take()
+                .as_array()
+                .cloned()
+                .unwrap_or_default(),
+        ),
+    };
+
+    // 只返回本地最新数据之后的变化，时间无法解析的数据交给 upsert 处理
+    let content: Vec<JsonValue> = content
+        .into_iter()
+        .filter(|item| {
+            match (
+                since,
+                item["update_time"].as_str().and_then(parse_update_time),
+            ) {
+                (Some(since), Some(update_time)) => update_time > since,
+                _ => true,
+            }
+        })
+        .collect();
+    Ok(json!({"status": status, "message": message, "content": content}))
+}
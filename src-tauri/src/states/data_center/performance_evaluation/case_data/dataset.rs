@@ -0,0 +1,93 @@
+use crate::states::data_center::dataset::Dataset;
+use crate::states::data_center::error::CustomError;
+use duckdb::Error::InvalidColumnName;
+use duckdb::ToSql;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseRecord {
+    pub id: Option<i64>,
+    pub 项目名称: String,
+    pub 项目类型: String,
+    pub 内容: JsonValue,
+    pub editor: JsonValue,
+    pub 文件路径: String,
+    pub update_time: String,
+}
+
+pub struct PerformanceEvaluationCaseDataset;
+
+impl Dataset for PerformanceEvaluationCaseDataset {
+    const NAME: &'static str = "预算绩效管理案例库";
+    const TABLE_NAME: &'static str = "预算绩效管理案例库";
+    const SYNC_ENDPOINT: &'static str = "http://www.baidu.com";
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "项目名称",
+        "项目类型",
+        "内容",
+        "editor",
+        "文件路径",
+        "update_time",
+    ];
+    const CONFLICT_COLUMNS: &'static [&'static str] = &["项目名称", "项目类型"];
+    const UPDATE_TIME_COLUMN: &'static str = "update_time";
+
+    type Row = CaseRecord;
+
+    fn from_json(pending_data: &JsonValue) -> Result<CaseRecord, CustomError> {
+        let 项目名称 = pending_data["项目名称"].as_str().ok_or_else(|| {
+            CustomError::DuckDBError(InvalidColumnName(
+                "PerformanceEvaluationCaseDataState: 未传入项目名称".to_string(),
+            ))
+        })?;
+        let 项目类型 = pending_data["项目类型"].as_str().ok_or_else(|| {
+            CustomError::DuckDBError(InvalidColumnName(
+                "PerformanceEvaluationCaseDataState: 未传入项目类型".to_string(),
+            ))
+        })?;
+        let 文件路径 = pending_data["文件路径"].as_str().ok_or_else(|| {
+            CustomError::DuckDBError(InvalidColumnName(
+                "PerformanceEvaluationCaseDataState: 未传入文件路径".to_string(),
+            ))
+        })?;
+
+        Ok(CaseRecord {
+            id: pending_data["id"].as_i64(),
+            项目名称: 项目名称.to_string(),
+            项目类型: 项目类型.to_string(),
+            内容: pending_data["内容"].to_owned(),
+            editor: pending_data["editor"].to_owned(),
+            文件路径: 文件路径.to_string(),
+            update_time: pending_data["update_time"]
+                .as_str()
+                .unwrap_or("")
+                .to_string(),
+        })
+    }
+
+    fn from_db_row(row: &duckdb::Row) -> Result<CaseRecord, duckdb::Error> {
+        Ok(CaseRecord {
+            id: Some(row.get(0)?),
+            项目名称: row.get(1)?,
+            项目类型: row.get(2)?,
+            内容: row.get(3)?,
+            editor: row.get(4)?,
+            文件路径: row.get(5)?,
+            update_time: row.get(6)?,
+        })
+    }
+
+    fn to_params(row: &CaseRecord) -> Vec<Box<dyn ToSql>> {
+        vec![
+            Box::new(row.id),
+            Box::new(row.项目名称.clone()),
+            Box::new(row.项目类型.clone()),
+            Box::new(row.内容.clone()),
+            Box::new(row.editor.clone()),
+            Box::new(row.文件路径.clone()),
+            Box::new(row.update_time.clone()),
+        ]
+    }
+}
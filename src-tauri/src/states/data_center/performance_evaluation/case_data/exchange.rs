@@ -0,0 +1,253 @@
+use super::dataset::PerformanceEvaluationCaseDataset;
+use crate::states::data_center::dataset::Dataset;
+use crate::states::data_center::error::CustomError;
+use crate::states::data_center::sql_console::canonical_select;
+use duckdb::{params, Connection};
+use serde::Serialize;
+use std::path::Path;
+
+// 外部数据集的视图都放在 external schema 中，查询时写作 external.名称
+pub const EXTERNAL_SCHEMA: &str = "external";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ExternalFormat {
+    Parquet,
+    Csv,
+}
+
+impl ExternalFormat {
+    fn as_str(self) -> &'static str {
+        match self {
+            ExternalFormat::Parquet => "parquet",
+            ExternalFormat::Csv => "csv",
+        }
+    }
+
+    fn from_path(path: &Path) -> Result<Self, CustomError> {
+        let extension = path
+            .extension()
+            .map(|extension| extension.to_string_lossy().to_lowercase());
+        match extension.as_deref() {
+            Some("parquet") => Ok(ExternalFormat::Parquet),
+            Some("csv") | Some("tsv") => Ok(ExternalFormat::Csv),
+            _ => Err(CustomError::InvalidQuery(format!(
+                "只支持 Parquet 和 CSV 文件: {}",
+                path.display()
+            ))),
+        }
+    }
+
+    fn reader(self) -> &'static str {
+        match self {
+            ExternalFormat::Parquet => "read_parquet",
+            ExternalFormat::Csv => "read_csv_auto",
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ExternalDataset {
+    pub name: String,
+    // 在查询中引用的视图名，例如 external."预算数据"
+    pub view: String,
+    pub path: String,
+    pub format: ExternalFormat,
+    pub attach_time: String,
+}
+
+// COPY 和视图定义中的文件路径不能使用参数绑定，按 SQL 字符串转义
+fn quote_literal(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "''"))
+}
+
+fn quote_identifier(value: &str) -> String {
+    format!("\"{}\"", value.replace('"', "\"\""))
+}
+
+fn view_name(name: &str) -> String {
+    format!("{}.{}", EXTERNAL_SCHEMA, quote_identifier(name))
+}
+
+// 将案例库或任意 SELECT 查询的结果导出为 Parquet 文件，返回导出的行数。
+// 与查询控制台一样在事务中执行后回滚，查询中有副作用的函数不会留下修改。
+pub fn export_parquet(
+    db: &Connection,
+    sql: Option<&str>,
+    destination: &Path,
+) -> Result<i64, CustomError> {
+    let query = match sql {
+        Some(sql) => canonical_select(db, sql)?,
+        None => format!(
+            "SELECT * FROM {} ORDER BY id",
+            PerformanceEvaluationCaseDataset::TABLE_NAME
+        ),
+    };
+    let destination = quote_literal(&destination.to_string_lossy());
+    db.execute_batch("BEGIN TRANSACTION;")?;
+    // COPY ... TO 返回写入文件的行数
+    let exported = db.execute(
+        &format!(
+            "COPY (\n{}\n) TO {} (FORMAT PARQUET, COMPRESSION ZSTD)",
+            query, destination
+        ),
+        [],
+    );
+    db.execute_batch("ROLLBACK;")?;
+    Ok(exported? as i64)
+}
+
+// 名称会作为视图名出现在查询中，只允许字母、数字（包括中文）和下划线
+fn validate_name(name: &str) -> Result<(), CustomError> {
+    if name.is_empty() || !name.chars().all(|c| c.is_alphanumeric() || c == '_') {
+        return Err(CustomError::InvalidQuery(format!(
+            "数据集名称只能包含字母、数字和下划线: {}",
+            name
+        )));
+    }
+    Ok(())
+}
+
+// 以只读视图的方式挂载本地 Parquet/CSV 文件，数据仍保存在原文件中，可以与案例库联合查询。
+// 同名的数据集会被替换。视图创建后立即读取一次，文件无法读取时不保留视图。
+pub fn attach(db: &Connection, name: &str, path: &Path) -> Result<ExternalDataset, CustomError> {
+    validate_name(name)?;
+    if !path.is_file() {
+        return Err(CustomError::NotFound(format!("文件 {}", path.display())));
+    }
+    let format = ExternalFormat::from_path(path)?;
+    let source = path.to_string_lossy().to_string();
+    let view = view_name(name);
+
+    db.execute_batch("BEGIN TRANSACTION;")?;
+    let attached = (|| -> Result<(), CustomError> {
+        db.execute_batch(&format!(
+            "CREATE OR REPLACE VIEW {} AS SELECT * FROM {}({});\nSELECT * FROM {} LIMIT 0;",
+            view,
+            format.reader(),
+            quote_literal(&source),
+            view
+        ))?;
+        db.execute(
+            "INSERT OR REPLACE INTO 外部数据集 (名称, 文件路径, 格式, attach_time) VALUES (?, ?, ?, current_timestamp)",
+            params![name, source, format.as_str()],
+        )?;
+        Ok(())
+    })();
+    match attached {
+        Ok(()) => db.execute_batch("COMMIT;")?,
+        Err(err) => {
+            let _ = db.execute_batch("ROLLBACK;");
+            return Err(err);
+        }
+    }
+
+    log::info!(dataset = name, format = format.as_str(); "已挂载外部数据集");
+    list(db)?
+        .into_iter()
+        .find(|dataset| dataset.name == name)
+        .ok_or_else(|| CustomError::NotFound(format!("外部数据集 {}", name)))
+}
+
+pub fn list(db: &Connection) -> Result<Vec<ExternalDataset>, CustomError> {
+    let mut stmt = db.prepare(
+        "SELECT 名称, 文件路径, 格式, attach_time::VARCHAR FROM 外部数据集 ORDER BY 名称",
+    )?;
+    let mut rows = stmt.query([])?;
+    let mut datasets = Vec::new();
+    while let Some(row) = rows.next()? {
+        let name: String = row.get(0)?;
+        let format = match row.get::<usize, String>(2)?.as_str() {
+            "parquet" => ExternalFormat::Parquet,
+            _ => ExternalFormat::Csv,
+        };
+        datasets.push(ExternalDataset {
+            view: view_name(&name),
+            name,
+            path: row.get(1)?,
+            format,
+            attach_time: row.get(3)?,
+        });
+    }
+    Ok(datasets)
+}
+
+// 只删除视图和记录，不删除原文件
+pub fn detach(db: &Connection, name: &str) -> Result<(), CustomError> {
+    validate_name(name)?;
+    db.execute_batch("BEGIN TRANSACTION;")?;
+    let detached = (|| -> Result<usize, CustomError> {
+        db.execute_batch(&format!("DROP VIEW IF EXISTS {};", view_name(name)))?;
+        Ok(db.execute("DELETE FROM 外部数据集 WHERE 名称 = ?", params![name])?)
+    })();
+    match detached {
+        Ok(0) => {
+            let _ = db.execute_batch("ROLLBACK;");
+            Err(CustomError::NotFound(format!("外部数据集 {}", name)))
+        }
+        Ok(_) => Ok(db.execute_batch("COMMIT;")?),
+        Err(err) => {
+            let _ = db.execute_batch("ROLLBACK;");
+            Err(err)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::migrations::open_test_database;
+    use super::*;
+    use crate::states::data_center::sql_console::run_read_only_query;
+    use serde_json::json;
+
+    #[test]
+    fn test_parquet_export_and_external_dataset() {
+        let db = open_test_database();
+        db.execute_batch(
+            "INSERT INTO 预算绩效管理案例库 (项目名称, 项目类型) VALUES ('项目一', '类型'), ('项目二', '类型');",
+        )
+        .unwrap();
+
+        let dir = std::env::temp_dir().join(format!("case_exchange_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let parquet = dir.join("cases.parquet");
+        assert_eq!(export_parquet(&db, None, &parquet).unwrap(), 2);
+        let filtered = export_parquet(
+            &db,
+            Some("SELECT 项目名称 FROM 预算绩效管理案例库 WHERE 项目名称 = '项目二';"),
+            &dir.join("filtered.parquet"),
+        )
+        .unwrap();
+        assert_eq!(filtered, 1);
+        for sql in [
+            "DELETE FROM 预算绩效管理案例库",
+            "SELECT * FROM read_csv_auto('/etc/passwd')",
+        ] {
+            let result = export_parquet(&db, Some(sql), &parquet);
+            assert!(
+                matches!(result, Err(CustomError::InvalidQuery(_))),
+                "{}",
+                sql
+            );
+        }
+        let csv = dir.join("预算.csv");
+        std::fs::write(&csv, "项目名称,预算金额\n项目一,100\n项目三,300\n").unwrap();
+        let dataset = attach(&db, "预算", &csv).unwrap();
+        assert_eq!(dataset.view, "external.\"预算\"");
+
+        let joined = run_read_only_query(
+            &db,
+            "SELECT c.项目名称, b.预算金额 FROM 预算绩效管理案例库 c JOIN external.预算 b USING (项目名称)",
+            10,
+        )
+        .unwrap();
+        assert_eq!(joined.rows, vec![vec![json!("项目一"), json!(100)]]);
+
+        let missing = attach(&db, "缺失", &dir.join("missing.parquet"));
+        assert!(matches!(missing, Err(CustomError::NotFound(_))));
+
+        detach(&db, "预算").unwrap();
+        assert!(list(&db).unwrap().is_empty());
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
@@ -0,0 +1,149 @@
+use duckdb::Connection;
+
+// 按顺序执行的数据库迁移，已执行的版本记录在 schema_version 表中。
+// 新增表结构变更时只能在末尾追加，不能修改已发布的迁移。
+const MIGRATIONS: &[(i64, &str)] = &[
+    (
+        1,
+        "
+    CREATE SEQUENCE IF NOT EXISTS 预算绩效管理案例库_id_seq;
+    CREATE TABLE IF NOT EXISTS 预算绩效管理案例库(
+        id INTEGER DEFAULT nextval('预算绩效管理案例库_id_seq') PRIMARY KEY,
+        项目名称 VARCHAR,
+        项目类型 VARCHAR ,
+        内容 JSON,
+        editor JSON,
+        文件路径 VARCHAR,
+        update_time TIMESTAMP WITH TIME ZONE,
+        UNIQUE(项目名称, 项目类型)
+    );
+    ",
+    ),
+    // 本地缓存的案例模板
+    (
+        2,
+        "
+    CREATE TABLE IF NOT EXISTS 预算绩效管理案例模板库(
+        id INTEGER PRIMARY KEY,
+        模板名称 VARCHAR,
+        项目类型 VARCHAR,
+        版本 INTEGER,
+        内容 JSON,
+        update_time TIMESTAMP WITH TIME ZONE,
+        UNIQUE(模板名称, 项目类型)
+    );
+    ",
+    ),
+    // 案例附件的下载状态，文件按 SHA-256 保存在应用数据目录中
+    (
+        3,
+        "
+    CREATE TABLE IF NOT EXISTS 预算绩效管理案例附件(
+        case_id INTEGER PRIMARY KEY,
+        source VARCHAR,
+        sha256 VARCHAR,
+        stored_name VARCHAR,
+        size BIGINT,
+        status VARCHAR,
+        error VARCHAR,
+        update_time TIMESTAMP WITH TIME ZONE
+    );
+    ",
+    ),
+    // 挂载的外部 Parquet/CSV 文件，视图放在 external schema 中
+    (
+        4,
+        "
+    CREATE SCHEMA IF NOT EXISTS external;
+    CREATE TABLE IF NOT EXISTS 外部数据集(
+        名称 VARCHAR PRIMARY KEY,
+        文件路径 VARCHAR,
+        格式 VARCHAR,
+        attach_time TIMESTAMP WITH TIME ZONE
+    );
+    ",
+    ),
+    // 各数据集的增量同步进度，与数据行的 update_time 分开保存，本地编辑不影响同步
+    (
+        5,
+        "
+    CREATE TABLE IF NOT EXISTS sync_cursor(
+        dataset VARCHAR PRIMARY KEY,
+        update_time VARCHAR
+    );
+    ",
+    ),
+    // 案例模板增加 JSON Schema，用于校验案例内容
+    (
+        6,
+        "
+    ALTER TABLE 预算绩效管理案例模板库 ADD COLUMN IF NOT EXISTS 结构定义 JSON;
+    ",
+    ),
+];
+
+pub fn schema_version(db: &Connection) -> Result<i64, duckdb::Error> {
+    db.execute_batch(
+        "CREATE TABLE IF NOT EXISTS schema_version(version BIGINT PRIMARY KEY, applied_at TIMESTAMP WITH TIME ZONE DEFAULT current_timestamp);",
+    )?;
+    db.query_row(
+        "SELECT coalesce(max(version), 0) FROM schema_version",
+        [],
+        |row| row.get::<usize, i64>(0),
+    )
+}
+
+pub fn run_migrations(db: &Connection) -> Result<i64, duckdb::Error> {
+    let mut current_version = schema_version(db)?;
+    for (version, sql) in MIGRATIONS {
+        if *version <= current_version {
+            continue;
+        }
+        // 版本记录与迁移在同一个事务中提交，中途退出时不会重复执行已提交的迁移
+        db.execute_batch(&format!(
+            "BEGIN;\n{}\nINSERT INTO schema_version (version) VALUES ({});\nCOMMIT;",
+            sql, version
+        ))
+        .map_err(|err| {
+            let _ = db.execute_batch("ROLLBACK;");
+            err
+        })?;
+        current_version = *version;
+    }
+    Ok(current_version)
+}
+
+// 报表、查询和导入导出的测试直接使用迁移后的内存数据库，不需要完整的案例库状态
+#[cfg(test)]
+pub fn open_test_database() -> Connection {
+    let db = Connection::open_in_memory().unwrap();
+    run_migrations(&db).unwrap();
+    db
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_run_migrations_is_idempotent() {
+        let db = Connection::open_in_memory().unwrap();
+        let latest = MIGRATIONS.last().unwrap().0;
+        assert_eq!(run_migrations(&db).unwrap(), latest);
+        db.execute_batch(
+            "INSERT INTO 预算绩效管理案例库 (项目名称, 项目类型) VALUES ('项目一', '类型');",
+        )
+        .unwrap();
+
+        assert_eq!(run_migrations(&db).unwrap(), latest);
+        let (versions, next_id): (i64, i64) = db
+            .query_row(
+                "SELECT (SELECT count(*) FROM schema_version), nextval('预算绩效管理案例库_id_seq')",
+                [],
+                |row| Ok((row.get(0)?, row.get(1)?)),
+            )
+            .unwrap();
+        assert_eq!(versions, latest);
+        assert_eq!(next_id, 2);
+    }
+}
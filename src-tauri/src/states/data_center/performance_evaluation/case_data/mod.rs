@@ -1 +1,8 @@
+pub mod analytics;
+pub mod attachment;
+pub mod backend;
 pub mod database;
+pub mod dataset;
+pub mod exchange;
+pub mod migrations;
+pub mod validation;
@@ -0,0 +1,178 @@
+use super::dataset::CaseRecord;
+use crate::states::data_center::error::{CustomError, ValidationIssue};
+use crate::states::data_center::performance_evaluation::case_template::dataset::CaseTemplateRecord;
+use duckdb::{params, Connection};
+use jsonschema::Validator;
+use serde_json::Value as JsonValue;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+
+// 模板 id、版本和更新时间，任意一项变化都说明结构定义可能已经改变
+type TemplateRevision = (i64, Option<i64>, Option<String>);
+
+// 编译结构定义的开销远大于校验本身，按项目类型缓存编译结果，同步和导入时不必逐行重新编译。
+// 缓存属于打开的案例库，不同数据库之间互不影响
+#[derive(Default)]
+pub struct ValidatorCache {
+    validators: Mutex<HashMap<String, (TemplateRevision, Arc<Validator>)>>,
+}
+
+impl ValidatorCache {
+    // 同一项目类型有多个模板时，使用版本最高且带有结构定义的模板
+    pub fn validator_for_project_type(
+        &self,
+        db: &Connection,
+        project_type: &str,
+    ) -> Result<Option<Arc<Validator>>, CustomError> {
+        let mut stmt = db.prepare(
+            "SELECT id, 版本, update_time::VARCHAR, 结构定义 FROM 预算绩效管理案例模板库 WHERE 项目类型 = ? AND 结构定义 IS NOT NULL ORDER BY 版本 DESC, update_time DESC",
+        )?;
+        let mut rows = stmt.query(params![project_type])?;
+        while let Some(row) = rows.next()? {
+            let revision: TemplateRevision = (row.get(0)?, row.get(1)?, row.get(2)?);
+            if let Some((cached, validator)) = self.validators.lock().unwrap().get(project_type) {
+                if *cached == revision {
+                    return Ok(Some(Arc::clone(validator)));
+                }
+            }
+            let schema: JsonValue = row.get(3)?;
+            if schema.is_null() {
+                continue;
+            }
+            let validator = Arc::new(compile_schema(&schema)?);
+            self.validators
+                .lock()
+                .unwrap()
+                .insert(project_type.to_string(), (revision, Arc::clone(&validator)));
+            return Ok(Some(validator));
+        }
+        Ok(None)
+    }
+}
+
+pub fn compile_schema(schema: &JsonValue) -> Result<Validator, CustomError> {
+    jsonschema::validator_for(schema).map_err(|err| CustomError::InvalidSchema(err.to_string()))
+}
+
+// 返回所有不满足 schema 的位置，路径以 /内容 开头
+pub fn validate_content(validator: &Validator, content: &JsonValue) -> Vec<ValidationIssue> {
+    validator
+        .iter_errors(content)
+        .map(|err| ValidationIssue {
+            path: format!("/内容{}", err.instance_path),
+            message: err.to_string(),
+        })
+        .collect()
+}
+
+pub fn case_issues(
+    db: &Connection,
+    validators: &ValidatorCache,
+    case: &CaseRecord,
+) -> Result<Vec<ValidationIssue>, CustomError> {
+    let mut issues = Vec::new();
+    if !(case.editor.is_object() || case.editor.is_null()) {
+        issues.push(ValidationIssue {
+            path: "/editor".to_string(),
+            message: "editor 必须是对象".to_string(),
+        });
+    }
+    if let Some(validator) = validators.validator_for_project_type(db, &case.项目类型)? {
+        issues.extend(validate_content(&validator, &case.内容));
+    }
+    Ok(issues)
+}
+
+// 案例写入前的校验，同步、导入和本地编辑共用
+pub fn validate_case(
+    db: &Connection,
+    validators: &ValidatorCache,
+    case: &CaseRecord,
+) -> Result<(), CustomError> {
+    let issues = case_issues(db, validators, case)?;
+    if issues.is_empty() {
+        Ok(())
+    } else {
+        Err(CustomError::ValidationError(issues))
+    }
+}
+
+// 模板的结构定义本身必须是合法的 JSON Schema，否则该类型的案例都无法保存
+pub fn validate_template(
+    _db: &Connection,
+    template: &CaseTemplateRecord,
+) -> Result<(), CustomError> {
+    if template.结构定义.is_null() {
+        return Ok(());
+    }
+    compile_schema(&template.结构定义)
+        .map(|_| ())
+        .map_err(|err| CustomError::InvalidSchema(format!("{}: {}", template.模板名称, err)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_validate_content_reports_paths() {
+        let schema = json!({
+            "type": "object",
+            "required": ["绩效指标"],
+            "properties": {
+                "绩效指标": {
+                    "type": "array",
+                    "items": {
+                        "type": "object",
+                        "properties": {"分值": {"type": "number"}}
+                    }
+                }
+            }
+        });
+
+        let validator = compile_schema(&schema).unwrap();
+        let issues = validate_content(
+            &validator,
+            &json!({"绩效指标": [{"分值": 10}, {"分值": "十"}]}),
+        );
+        assert_eq!(issues.len(), 1);
+        assert_eq!(issues[0].path, "/内容/绩效指标/1/分值");
+
+        let issues = validate_content(&validator, &json!({"绩效指标": []}));
+        assert!(issues.is_empty());
+    }
+
+    #[test]
+    fn test_validator_cache_follows_template_version() {
+        let db = Connection::open_in_memory().unwrap();
+        db.execute_batch(
+            "CREATE TABLE 预算绩效管理案例模板库(id INTEGER, 项目类型 VARCHAR, 版本 INTEGER, 结构定义 JSON, update_time TIMESTAMP WITH TIME ZONE);
+             INSERT INTO 预算绩效管理案例模板库 VALUES (1, '缓存测试', 1, '{\"type\": \"object\"}', '2024-01-01 00:00:00+00');",
+        )
+        .unwrap();
+
+        let validators = ValidatorCache::default();
+        let first = validators
+            .validator_for_project_type(&db, "缓存测试")
+            .unwrap()
+            .unwrap();
+        let second = validators
+            .validator_for_project_type(&db, "缓存测试")
+            .unwrap()
+            .unwrap();
+        assert!(Arc::ptr_eq(&first, &second));
+        assert!(!validate_content(&first, &json!([])).is_empty());
+
+        db.execute_batch(
+            "UPDATE 预算绩效管理案例模板库 SET 版本 = 2, 结构定义 = '{\"type\": \"array\"}';",
+        )
+        .unwrap();
+        let updated = validators
+            .validator_for_project_type(&db, "缓存测试")
+            .unwrap()
+            .unwrap();
+        assert!(!Arc::ptr_eq(&first, &updated));
+        assert!(validate_content(&updated, &json!([])).is_empty());
+    }
+}
@@ -0,0 +1,93 @@
+use crate::states::data_center::dataset::Dataset;
+use crate::states::data_center::error::CustomError;
+use duckdb::Error::InvalidColumnName;
+use duckdb::ToSql;
+use serde::Serialize;
+use serde_json::Value as JsonValue;
+
+#[derive(Debug, Clone, Serialize)]
+pub struct CaseTemplateRecord {
+    pub id: Option<i64>,
+    pub 模板名称: String,
+    pub 项目类型: String,
+    // 后端每次修改模板时递增
+    pub 版本: i64,
+    pub 内容: JsonValue,
+    // 该模板生成的案例内容需要满足的 JSON Schema，为空时不校验
+    pub 结构定义: JsonValue,
+    pub update_time: String,
+}
+
+pub struct PerformanceEvaluationCaseTemplateDataset;
+
+impl Dataset for PerformanceEvaluationCaseTemplateDataset {
+    const NAME: &'static str = "预算绩效管理案例模板库";
+    const TABLE_NAME: &'static str = "预算绩效管理案例模板库";
+    const SYNC_ENDPOINT: &'static str = "http://www.baidu.com";
+    const COLUMNS: &'static [&'static str] = &[
+        "id",
+        "模板名称",
+        "项目类型",
+        "版本",
+        "内容",
+        "结构定义",
+        "update_time",
+    ];
+    const CONFLICT_COLUMNS: &'static [&'static str] = &["模板名称", "项目类型"];
+    const UPDATE_TIME_COLUMN: &'static str = "update_time";
+    // 本地已有更高版本的模板时不被旧数据覆盖
+    const UPSERT_CONDITION: Option<&'static str> =
+        Some("excluded.版本 >= 预算绩效管理案例模板库.版本");
+
+    type Row = CaseTemplateRecord;
+
+    fn from_json(pending_data: &JsonValue) -> Result<CaseTemplateRecord, CustomError> {
+        let 模板名称 = pending_data["模板名称"].as_str().ok_or_else(|| {
+            CustomError::DuckDBError(InvalidColumnName(
+                "PerformanceEvaluationCaseTemplate: 未传入模板名称".to_string(),
+            ))
+        })?;
+        let 项目类型 = pending_data["项目类型"].as_str().ok_or_else(|| {
+            CustomError::DuckDBError(InvalidColumnName(
+                "PerformanceEvaluationCaseTemplate: 未传入项目类型".to_string(),
+            ))
+        })?;
+
+        Ok(CaseTemplateRecord {
+            id: pending_data["id"].as_i64(),
+            模板名称: 模板名称.to_string(),
+            项目类型: 项目类型.to_string(),
+            版本: pending_data["版本"].as_i64().unwrap_or(1),
+            内容: pending_data["内容"].to_owned(),
+            结构定义: pending_data["结构定义"].to_owned(),
+            update_time: pending_data["update_time"]
+                .as_str()
+                .unwrap_or("")
+                .to_string(),
+        })
+    }
+
+    fn from_db_row(row: &duckdb::Row) -> Result<CaseTemplateRecord, duckdb::Error> {
+        Ok(CaseTemplateRecord {
+            id: Some(row.get(0)?),
+            模板名称: row.get(1)?,
+            项目类型: row.get(2)?,
+            版本: row.get(3)?,
+            内容: row.get(4)?,
+            结构定义: row.get(5)?,
+            update_time: row.get(6)?,
+        })
+    }
+
+    fn to_params(row: &CaseTemplateRecord) -> Vec<Box<dyn ToSql>> {
+        vec![
+            Box::new(row.id),
+            Box::new(row.模板名称.clone()),
+            Box::new(row.项目类型.clone()),
+            Box::new(row.版本),
+            Box::new(row.内容.clone()),
+            Box::new(row.结构定义.clone()),
+            Box::new(row.update_time.clone()),
+        ]
+    }
+}
@@ -1 +1,2 @@
 pub mod case_data;
+pub mod case_template;
@@ -0,0 +1,208 @@
+use crate::states::data_center::error::CustomError;
+use crate::states::data_center::tabular::{query_tabular, TabularResult};
+use duckdb::{params, Connection};
+use serde_json::Value as JsonValue;
+use std::collections::HashSet;
+
+pub const DEFAULT_MAX_ROWS: usize = 1_000;
+pub const MAX_ROWS_LIMIT: usize = 10_000;
+pub const DEFAULT_TIMEOUT_SECS: u64 = 30;
+pub const MAX_TIMEOUT_SECS: u64 = 300;
+
+// 查询中可以引用的 schema：案例库所在的 main 和外部数据集视图所在的 external
+const ALLOWED_SCHEMAS: &[&str] = &["main", "external"];
+
+// 只允许单条 SELECT 语句（包括 WITH、VALUES 等查询），返回 DuckDB 重新生成的规范化 SQL。
+// 借助 DuckDB 的 json_serialize_sql 解析语句：它只能序列化 SELECT 语句，其他语句返回错误。
+// 控制台与应用共用数据库，无法单独关闭文件访问，因此检查语法树：
+// 不允许 read_csv 之类的表函数，表名只能是已有的表、视图或 CTE，避免直接读取本地文件。
+pub fn canonical_select(db: &Connection, sql: &str) -> Result<String, CustomError> {
+    let sql = sql.trim().trim_end_matches(';').trim_end();
+    let serialized: String = db.query_row("SELECT json_serialize_sql(?)", params![sql], |row| {
+        row.get(0)
+    })?;
+    let tree: JsonValue = serde_json::from_str(&serialized)
+        .map_err(|err| CustomError::InvalidQuery(err.to_string()))?;
+    if tree["error"].as_bool().unwrap_or(true) {
+        return Err(CustomError::InvalidQuery(format!(
+            "只允许执行 SELECT 查询: {}",
+            tree["error_message"].as_str().unwrap_or("无法解析")
+        )));
+    }
+    if tree["statements"].as_array().map(Vec::len) != Some(1) {
+        return Err(CustomError::InvalidQuery(
+            "一次只能执行一条查询语句".to_string(),
+        ));
+    }
+
+    let mut cte_names = HashSet::new();
+    collect_cte_names(&tree, &mut cte_names);
+    check_table_refs(db, &tree, &cte_names)?;
+
+    Ok(db.query_row(
+        "SELECT json_deserialize_sql(?::JSON)",
+        params![serialized],
+        |row| row.get(0),
+    )?)
+}
+
+fn collect_cte_names(node: &JsonValue, names: &mut HashSet<String>) {
+    match node {
+        JsonValue::Object(object) => {
+            if let Some(entries) = object.get("cte_map").and_then(|cte| cte["map"].as_array()) {
+                for entry in entries {
+                    if let Some(name) = entry["key"].as_str() {
+                        names.insert(name.to_lowercase());
+                    }
+                }
+            }
+            object
+                .values()
+                .for_each(|value| collect_cte_names(value, names));
+        }
+        JsonValue::Array(values) => values
+            .iter()
+            .for_each(|value| collect_cte_names(value, names)),
+        _ => {}
+    }
+}
+
+fn check_table_refs(
+    db: &Connection,
+    node: &JsonValue,
+    cte_names: &HashSet<String>,
+) -> Result<(), CustomError> {
+    match node {
+        JsonValue::Object(object) => {
+            match object.get("type").and_then(JsonValue::as_str) {
+                Some("TABLE_FUNCTION") => {
+                    return Err(CustomError::InvalidQuery(
+                        "查询中不能使用表函数，外部文件请先挂载为外部数据集".to_string(),
+                    ))
+                }
+                Some("BASE_TABLE") => check_base_table(db, node, cte_names)?,
+                _ => {}
+            }
+            for value in object.values() {
+                check_table_refs(db, value, cte_names)?;
+            }
+            Ok(())
+        }
+        JsonValue::Array(values) => values
+            .iter()
+            .try_for_each(|value| check_table_refs(db, value, cte_names)),
+        _ => Ok(()),
+    }
+}
+
+fn check_base_table(
+    db: &Connection,
+    table_ref: &JsonValue,
+    cte_names: &HashSet<String>,
+) -> Result<(), CustomError> {
+    let catalog = table_ref["catalog_name"].as_str().unwrap_or("");
+    let schema = table_ref["schema_name"].as_str().unwrap_or("");
+    let table = table_ref["table_name"].as_str().unwrap_or("");
+    if catalog.is_empty() && schema.is_empty() && cte_names.contains(&table.to_lowercase()) {
+        return Ok(());
+    }
+
+    let schema = if schema.is_empty() { "main" } else { schema };
+    let allowed = (catalog.is_empty() || catalog == current_database(db)?)
+        && ALLOWED_SCHEMAS.contains(&schema.to_lowercase().as_str())
+        && db.query_row(
+            "SELECT count(*) > 0 FROM information_schema.tables WHERE table_catalog = current_database() AND lower(table_schema) = lower(?) AND lower(table_name) = lower(?)",
+            params![schema, table],
+            |row| row.get::<usize, bool>(0),
+        )?;
+    if allowed {
+        Ok(())
+    } else {
+        // 不存在的表名会被 DuckDB 当作文件路径读取
+        Err(CustomError::InvalidQuery(format!(
+            "只能查询案例库中的表和外部数据集: {}",
+            table
+        )))
+    }
+}
+
+fn current_database(db: &Connection) -> Result<String, CustomError> {
+    Ok(db.query_row("SELECT current_database()", [], |row| row.get(0))?)
+}
+
+// 在只读的前提下执行用户输入的查询：校验为单条 SELECT，外层加上 LIMIT 让 DuckDB 提前结束扫描，
+// 并在事务中执行后回滚，nextval 之类有副作用的函数也不会留下修改。
+pub fn run_read_only_query(
+    db: &Connection,
+    sql: &str,
+    max_rows: usize,
+) -> Result<TabularResult, CustomError> {
+    let sql = canonical_select(db, sql)?;
+
+    // 多取一行用于判断是否被截断
+    let limited = format!("SELECT * FROM (\n{}\n) LIMIT {}", sql, max_rows + 1);
+    db.execute_batch("BEGIN TRANSACTION;")?;
+    let result = query_tabular(db, &limited, Vec::new(), max_rows);
+    db.execute_batch("ROLLBACK;")?;
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::states::data_center::performance_evaluation::case_data::migrations::open_test_database;
+    use serde_json::json;
+
+    #[test]
+    fn test_query_is_read_only() {
+        let db = open_test_database();
+        db.execute_batch(
+            "INSERT INTO 预算绩效管理案例库 (项目名称, 项目类型) VALUES ('项目一', '类型'), ('项目二', '类型'), ('项目三', '类型');",
+        )
+        .unwrap();
+
+        let result = run_read_only_query(
+            &db,
+            "SELECT 项目名称, id FROM 预算绩效管理案例库 ORDER BY id; -- 全部案例",
+            2,
+        )
+        .unwrap();
+        assert_eq!(result.columns.len(), 2);
+        assert_eq!(result.rows.len(), 2);
+        assert_eq!(result.rows[0][0], "项目一");
+        assert!(result.truncated);
+
+        let result = run_read_only_query(
+            &db,
+            "WITH 案例 AS (SELECT * FROM main.预算绩效管理案例库) SELECT count(*) FROM 案例",
+            10,
+        )
+        .unwrap();
+        assert_eq!(result.rows, vec![vec![json!(3)]]);
+
+        for sql in [
+            "DELETE FROM 预算绩效管理案例库",
+            "SELECT 1; DROP TABLE 预算绩效管理案例库",
+            "COPY 预算绩效管理案例库 TO 'cases.csv'",
+            "SELECT * FROM read_csv_auto('/etc/passwd')",
+            "SELECT * FROM (SELECT * FROM read_text('/etc/hosts'))",
+            "SELECT * FROM '/etc/passwd'",
+            "SELECT * FROM 不存在的表",
+        ] {
+            let result = run_read_only_query(&db, sql, 10);
+            assert!(
+                matches!(result, Err(CustomError::InvalidQuery(_))),
+                "{}",
+                sql
+            );
+        }
+        let count: i64 = db
+            .query_row(
+                "SELECT count(*) FROM 预算绩效管理案例库",
+                [],
+                |row| row.get(0),
+            )
+            .unwrap();
+        assert_eq!(count, 3);
+    }
+}
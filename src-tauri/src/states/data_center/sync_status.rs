@@ -0,0 +1,148 @@
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex, PoisonError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SyncPhase {
+    Idle,
+    // 正在从后端获取变化的数据
+    Fetching,
+    // 正在写入本地数据库
+    Applying,
+    Failed,
+    Done,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DatasetSyncStatus {
+    pub dataset: String,
+    pub phase: SyncPhase,
+    // 当前阶段的进度，applying 阶段为已写入的条数
+    pub processed: usize,
+    pub total: usize,
+    // 最近一次同步的统计
+    pub received: usize,
+    pub applied: usize,
+    pub rejected: usize,
+    pub started_at: Option<String>,
+    pub last_success_time: Option<String>,
+    pub last_error: Option<String>,
+}
+
+impl DatasetSyncStatus {
+    fn idle(dataset: &str) -> Self {
+        Self {
+            dataset: dataset.to_string(),
+            phase: SyncPhase::Idle,
+            processed: 0,
+            total: 0,
+            received: 0,
+            applied: 0,
+            rejected: 0,
+            started_at: None,
+            last_success_time: None,
+            last_error: None,
+        }
+    }
+}
+
+pub type SyncReporter = Arc<dyn Fn(&DatasetSyncStatus) + Send + Sync>;
+
+// applying 阶段每写入这么多条数据上报一次进度，避免逐条发送事件
+const PROGRESS_STEP: usize = 50;
+
+// 各数据集的同步状态：idle -> fetching -> applying -> done / failed。
+// 每次状态变化都会通过 reporter 通知前端。
+#[derive(Default)]
+pub struct SyncTracker {
+    statuses: Mutex<BTreeMap<String, DatasetSyncStatus>>,
+    reporter: Mutex<Option<SyncReporter>>,
+}
+
+impl SyncTracker {
+    pub fn set_reporter(&self, reporter: SyncReporter) {
+        *self.reporter.lock().unwrap_or_else(PoisonError::into_inner) = Some(reporter);
+    }
+
+    // 数据集在第一次同步前也显示为 idle
+    pub fn register(&self, dataset: &str) {
+        self.statuses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .entry(dataset.to_string())
+            .or_insert_with(|| DatasetSyncStatus::idle(dataset));
+    }
+
+    pub fn statuses(&self) -> Vec<DatasetSyncStatus> {
+        self.statuses
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .values()
+            .cloned()
+            .collect()
+    }
+
+    pub fn fetching(&self, dataset: &str) {
+        self.update(dataset, |status| {
+            status.phase = SyncPhase::Fetching;
+            status.processed = 0;
+            status.total = 0;
+            status.started_at = Some(now());
+        });
+    }
+
+    pub fn applying(&self, dataset: &str, processed: usize, total: usize) {
+        // 只在阶段开始、每 PROGRESS_STEP 条和最后一条时上报
+        if processed != 0 && processed != total && processed % PROGRESS_STEP != 0 {
+            return;
+        }
+        self.update(dataset, |status| {
+            status.phase = SyncPhase::Applying;
+            status.processed = processed;
+            status.total = total;
+        });
+    }
+
+    pub fn done(&self, dataset: &str, received: usize, applied: usize, rejected: usize) {
+        self.update(dataset, |status| {
+            status.phase = SyncPhase::Done;
+            status.received = received;
+            status.applied = applied;
+            status.rejected = rejected;
+            status.last_success_time = Some(now());
+            status.last_error = None;
+        });
+    }
+
+    pub fn failed(&self, dataset: &str, error: String) {
+        self.update(dataset, |status| {
+            status.phase = SyncPhase::Failed;
+            status.last_error = Some(error);
+        });
+    }
+
+    fn update(&self, dataset: &str, f: impl FnOnce(&mut DatasetSyncStatus)) {
+        let status = {
+            let mut statuses = self.statuses.lock().unwrap_or_else(PoisonError::into_inner);
+            let status = statuses
+                .entry(dataset.to_string())
+                .or_insert_with(|| DatasetSyncStatus::idle(dataset));
+            f(status);
+            status.clone()
+        };
+
+        let reporter = self
+            .reporter
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner)
+            .clone();
+        if let Some(reporter) = reporter {
+            reporter(&status);
+        }
+    }
+}
+
+fn now() -> String {
+    chrono::Local::now().to_rfc3339()
+}
@@ -0,0 +1,235 @@
+use crate::states::data_center::error::CustomError;
+use base64::Engine;
+use chrono::{DateTime, NaiveDate, NaiveTime};
+use duckdb::types::{TimeUnit, Value};
+use duckdb::{params_from_iter, Connection};
+use serde::Serialize;
+use serde_json::{json, Value as JsonValue};
+
+#[derive(Debug, Clone, Serialize)]
+pub struct TabularColumn {
+    pub name: String,
+    // DuckDB 返回的列类型，例如 Int64、Utf8
+    pub data_type: String,
+}
+
+// 报表和查询的表格结果，rows 中每一行的值与 columns 一一对应
+#[derive(Debug, Clone, Serialize)]
+pub struct TabularResult {
+    pub columns: Vec<TabularColumn>,
+    pub rows: Vec<Vec<JsonValue>>,
+    // 结果超过 max_rows 行时被截断
+    pub truncated: bool,
+}
+
+// 执行查询并转换为表格结果，最多读取 max_rows 行
+pub fn query_tabular(
+    db: &Connection,
+    sql: &str,
+    params: Vec<Value>,
+    max_rows: usize,
+) -> Result<TabularResult, CustomError> {
+    let mut stmt = db.prepare(sql)?;
+    let mut rows = stmt.query(params_from_iter(params))?;
+
+    // 列信息在语句执行后才能获取
+    let columns: Vec<TabularColumn> = match rows.as_ref() {
+        Some(stmt) => stmt
+            .column_names()
+            .into_iter()
+            .enumerate()
+            .map(|(index, name)| TabularColumn {
+                name,
+                data_type: stmt.column_type(index).to_string(),
+            })
+            .collect(),
+        None => Vec::new(),
+    };
+
+    let mut result = Vec::new();
+    let mut truncated = false;
+    while let Some(row) = rows.next()? {
+        if result.len() >= max_rows {
+            truncated = true;
+            break;
+        }
+        let mut values = Vec::with_capacity(columns.len());
+        for index in 0..columns.len() {
+            values.push(value_to_json(row.get::<usize, Value>(index)?));
+        }
+        result.push(values);
+    }
+
+    Ok(TabularResult {
+        columns,
+        rows: result,
+        truncated,
+    })
+}
+
+fn to_micros(unit: TimeUnit, value: i64) -> i64 {
+    match unit {
+        TimeUnit::Second => value.saturating_mul(1_000_000),
+        TimeUnit::Millisecond => value.saturating_mul(1_000),
+        TimeUnit::Microsecond => value,
+        TimeUnit::Nanosecond => value / 1_000,
+    }
+}
+
+// 能用 JSON 数字精确表示的小数输出为数字，否则输出为字符串，避免金额丢失精度
+fn decimal_to_json(text: String) -> JsonValue {
+    let normalized = if text.contains('.') {
+        text.trim_end_matches('0').trim_end_matches('.')
+    } else {
+        text.as_str()
+    };
+    match normalized.parse::<f64>() {
+        Ok(number) if number.is_finite() && number.to_string() == normalized => json!(number),
+        _ => json!(text),
+    }
+}
+
+// ISO 8601 时长，例如 1 年 2 个月 3 天 4 小时为 P1Y2M3DT4H
+fn interval_to_iso(months: i32, days: i32, nanos: i64) -> String {
+    let mut text = "P".to_string();
+    let (years, months) = (months / 12, months % 12);
+    for (amount, unit) in [
+        (years as i64, 'Y'),
+        (months as i64, 'M'),
+        (days as i64, 'D'),
+    ] {
+        if amount != 0 {
+            text.push_str(&format!("{}{}", amount, unit));
+        }
+    }
+    if nanos != 0 {
+        let sign = if nanos < 0 { "-" } else { "" };
+        let nanos = nanos.unsigned_abs();
+        let (hours, minutes) = (nanos / 3_600_000_000_000, nanos / 60_000_000_000 % 60);
+        let (seconds, fraction) = (nanos / 1_000_000_000 % 60, nanos % 1_000_000_000);
+        text.push('T');
+        if hours != 0 {
+            text.push_str(&format!("{}{}H", sign, hours));
+        }
+        if minutes != 0 {
+            text.push_str(&format!("{}{}M", sign, minutes));
+        }
+        if seconds != 0 || fraction != 0 {
+            let fraction = format!("{:09}", fraction);
+            let fraction = fraction.trim_end_matches('0');
+            if fraction.is_empty() {
+                text.push_str(&format!("{}{}S", sign, seconds));
+            } else {
+                text.push_str(&format!("{}{}.{}S", sign, seconds, fraction));
+            }
+        }
+    }
+    if text == "P" {
+        text.push_str("T0S");
+    }
+    text
+}
+
+// 将 DuckDB 的值转换为 JSON，超出 JSON 数字范围的整数和无法直接表示的类型转为字符串。
+// STRUCT 和 MAP 转为对象，BLOB 转为 Base64，时间和时长转为 ISO 8601 字符串。
+pub fn value_to_json(value: Value) -> JsonValue {
+    match value {
+        Value::Null => JsonValue::Null,
+        Value::Boolean(value) => json!(value),
+        Value::TinyInt(value) => json!(value),
+        Value::SmallInt(value) => json!(value),
+        Value::Int(value) => json!(value),
+        Value::BigInt(value) => json!(value),
+        Value::HugeInt(value) => match i64::try_from(value) {
+            Ok(value) => json!(value),
+            Err(_) => json!(value.to_string()),
+        },
+        Value::UTinyInt(value) => json!(value),
+        Value::USmallInt(value) => json!(value),
+        Value::UInt(value) => json!(value),
+        Value::UBigInt(value) => json!(value),
+        Value::Float(value) => json!(value),
+        Value::Double(value) => json!(value),
+        Value::Decimal(value) => decimal_to_json(value.to_string()),
+        Value::Text(value) | Value::Enum(value) => json!(value),
+        Value::Blob(value) => json!(base64::engine::general_purpose::STANDARD.encode(value)),
+        Value::Timestamp(unit, value) => DateTime::from_timestamp_micros(to_micros(unit, value))
+            .map(|time| json!(time.to_rfc3339()))
+            .unwrap_or(JsonValue::Null),
+        Value::Time64(unit, value) => {
+            let micros = to_micros(unit, value);
+            u32::try_from(micros.div_euclid(1_000_000))
+                .ok()
+                .and_then(|seconds| {
+                    let nanos = (micros.rem_euclid(1_000_000) * 1_000) as u32;
+                    NaiveTime::from_num_seconds_from_midnight_opt(seconds, nanos)
+                })
+                .map(|time| json!(time.to_string()))
+                .unwrap_or(JsonValue::Null)
+        }
+        Value::Interval {
+            months,
+            days,
+            nanos,
+        } => json!(interval_to_iso(months, days, nanos)),
+        Value::Date32(days) => NaiveDate::from_ymd_opt(1970, 1, 1)
+            .and_then(|epoch| epoch.checked_add_signed(chrono::Duration::days(days.into())))
+            .map(|date| json!(date.to_string()))
+            .unwrap_or(JsonValue::Null),
+        Value::List(values) | Value::Array(values) => {
+            JsonValue::Array(values.into_iter().map(value_to_json).collect())
+        }
+        Value::Struct(fields) => JsonValue::Object(
+            fields
+                .iter()
+                .map(|(name, value)| (name.clone(), value_to_json(value.clone())))
+                .collect(),
+        ),
+        // JSON 对象的键只能是字符串，非字符串的键转为对应的 JSON 文本
+        Value::Map(entries) => JsonValue::Object(
+            entries
+                .iter()
+                .map(|(key, value)| {
+                    let key = match value_to_json(key.clone()) {
+                        JsonValue::String(key) => key,
+                        key => key.to_string(),
+                    };
+                    (key, value_to_json(value.clone()))
+                })
+                .collect(),
+        ),
+        Value::Union(value) => value_to_json(*value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_value_to_json() {
+        let db = Connection::open_in_memory().unwrap();
+        let result = query_tabular(
+            &db,
+            "SELECT 12.50::DECIMAL(10, 2), 12345678901234567.89::DECIMAL(38, 2), {'名称': '产出指标', '分值': 20},
+                MAP {'a': 1}, '\\x01\\x02'::BLOB, TIME '10:30:00.5',
+                INTERVAL 1 YEAR + INTERVAL 2 MONTH + INTERVAL 3 DAY + INTERVAL 4 HOUR, INTERVAL 0 DAY",
+            Vec::new(),
+            1,
+        )
+        .unwrap();
+        assert_eq!(
+            result.rows[0],
+            vec![
+                json!(12.5),
+                json!("12345678901234567.89"),
+                json!({"名称": "产出指标", "分值": 20}),
+                json!({"a": 1}),
+                json!("AQI="),
+                json!("10:30:00.500"),
+                json!("P1Y2M3DT4H"),
+                json!("PT0S"),
+            ]
+        );
+    }
+}
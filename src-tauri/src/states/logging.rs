@@ -0,0 +1,264 @@
+use log::LevelFilter;
+use serde::Serialize;
+use std::io::{Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use tauri::plugin::TauriPlugin;
+use tauri::{AppHandle, Manager, Runtime};
+use tauri_plugin_dialog::DialogExt;
+use tauri_plugin_log::{RotationStrategy, Target, TargetKind};
+use thiserror::Error;
+
+pub const LOG_FILE_NAME: &str = "app";
+// 单个日志文件的大小上限，超过后轮转为新文件
+const MAX_LOG_FILE_SIZE: u128 = 5 * 1024 * 1024;
+// 轮转后只保留最近的几个日志文件，由插件在轮转时删除更早的文件
+const MAX_LOG_FILES: usize = 10;
+const DEFAULT_LEVEL: LevelFilter = LevelFilter::Info;
+
+#[derive(Error, Debug)]
+pub enum LoggingError {
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Tauri error: {0}")]
+    TauriError(#[from] tauri::Error),
+    #[error("Invalid log level: {0}")]
+    InvalidLevel(String),
+    #[error("Join error: {0}")]
+    JoinError(#[from] tokio::task::JoinError),
+}
+
+// 命令返回的错误需要能序列化给前端
+impl Serialize for LoggingError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+// 日志同时输出到终端和应用日志目录。插件本身不做过滤，运行时通过 log::set_max_level 调整级别。
+// 结构化字段使用 log 的 key-value 语法，例如 log::info!(operation_id = id; "...")，追加在消息末尾。
+pub fn plugin<R: Runtime>() -> TauriPlugin<R> {
+    tauri_plugin_log::Builder::new()
+        .clear_targets()
+        .targets([
+            Target::new(TargetKind::Stdout),
+            Target::new(TargetKind::LogDir {
+                file_name: Some(LOG_FILE_NAME.to_string()),
+            }),
+        ])
+        .level(LevelFilter::Trace)
+        .max_file_size(MAX_LOG_FILE_SIZE)
+        .rotation_strategy(RotationStrategy::KeepSome(MAX_LOG_FILES))
+        .format(|out, message, record| {
+            let mut fields = Fields(String::new());
+            let _ = record.key_values().visit(&mut fields);
+            out.finish(format_args!(
+                "[{}][{}][{}] {}{}",
+                chrono::Local::now().format("%Y-%m-%d %H:%M:%S%.3f"),
+                record.level(),
+                record.target(),
+                message,
+                fields.0
+            ))
+        })
+        .build()
+}
+
+struct Fields(String);
+
+impl<'kvs> log::kv::VisitSource<'kvs> for Fields {
+    fn visit_pair(
+        &mut self,
+        key: log::kv::Key<'kvs>,
+        value: log::kv::Value<'kvs>,
+    ) -> Result<(), log::kv::Error> {
+        self.0.push_str(&format!(" {}={}", key, value));
+        Ok(())
+    }
+}
+
+// 应用启动时调用：设置默认级别
+pub fn init(_app_handle: &AppHandle) {
+    log::set_max_level(DEFAULT_LEVEL);
+}
+
+pub fn level() -> String {
+    log::max_level().to_string().to_lowercase()
+}
+
+pub fn set_level(level: &str) -> Result<(), LoggingError> {
+    let level =
+        LevelFilter::from_str(level).map_err(|_| LoggingError::InvalidLevel(level.to_string()))?;
+    log::set_max_level(level);
+    log::info!("日志级别调整为 {}", level);
+    Ok(())
+}
+
+// 当前日志文件末尾最多 max_bytes 字节中的最后 max_lines 行，崩溃时使用，不遍历轮转后的文件
+pub fn current_log_tail(log_dir: &Path, max_bytes: u64, max_lines: usize) -> Vec<String> {
+    let path = log_dir.join(format!("{}.log", LOG_FILE_NAME));
+    let read_tail = || -> std::io::Result<String> {
+        let mut file = std::fs::File::open(&path)?;
+        let start = file.metadata()?.len().saturating_sub(max_bytes);
+        file.seek(SeekFrom::Start(start))?;
+        let mut content = Vec::new();
+        file.take(max_bytes).read_to_end(&mut content)?;
+        let content = String::from_utf8_lossy(&content).into_owned();
+        // 从文件中间开始读取时第一行可能不完整
+        Ok(match (start, content.find('\n')) {
+            (0, _) | (_, None) => content,
+            (_, Some(newline)) => content[newline + 1..].to_string(),
+        })
+    };
+    let content = read_tail().unwrap_or_default();
+    let lines: Vec<&str> = content.lines().collect();
+    lines[lines.len().saturating_sub(max_lines)..]
+        .iter()
+        .map(|line| line.to_string())
+        .collect()
+}
+
+// 日志目录中的日志文件，按修改时间从旧到新排列
+fn log_files(log_dir: &Path) -> std::io::Result<Vec<PathBuf>> {
+    if !log_dir.exists() {
+        return Ok(Vec::new());
+    }
+    let mut files = Vec::new();
+    for entry in std::fs::read_dir(log_dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        let is_log = path
+            .file_name()
+            .map(|name| name.to_string_lossy())
+            .map(|name| name.starts_with(LOG_FILE_NAME) && name.ends_with(".log"))
+            .unwrap_or(false);
+        if is_log {
+            files.push((entry.metadata()?.modified()?, path));
+        }
+    }
+    files.sort();
+    Ok(files.into_iter().map(|(_, path)| path).collect())
+}
+
+// 最近的 max_lines 行日志，跨越轮转后的多个文件
+pub fn recent_log_lines(
+    app_handle: &AppHandle,
+    max_lines: usize,
+) -> Result<Vec<String>, LoggingError> {
+    Ok(recent_lines_in(
+        &app_handle.path().app_log_dir()?,
+        max_lines,
+    )?)
+}
+
+fn recent_lines_in(log_dir: &Path, max_lines: usize) -> std::io::Result<Vec<String>> {
+    let mut lines = Vec::new();
+    for path in log_files(log_dir)?.iter().rev() {
+        let content = std::fs::read(path)?;
+        let mut file_lines: Vec<String> = String::from_utf8_lossy(&content)
+            .lines()
+            .map(str::to_string)
+            .collect();
+        let take = max_lines.saturating_sub(lines.len()).min(file_lines.len());
+        let mut tail = file_lines.split_off(file_lines.len() - take);
+        tail.append(&mut lines);
+        lines = tail;
+        if lines.len() >= max_lines {
+            break;
+        }
+    }
+    Ok(lines)
+}
+
+// 导出最近的日志用于提交工单，保存位置由用户在对话框中选择，取消时返回 None
+pub async fn export_recent_logs(
+    app_handle: &AppHandle,
+    max_lines: usize,
+) -> Result<Option<PathBuf>, LoggingError> {
+    let file_name = format!("logs-{}.log", chrono::Local::now().format("%Y%m%d-%H%M%S"));
+    let (sender, receiver) = tokio::sync::oneshot::channel();
+    app_handle
+        .dialog()
+        .file()
+        .set_file_name(&file_name)
+        .add_filter("log", &["log", "txt"])
+        .save_file(move |path| {
+            let _ = sender.send(path);
+        });
+    let Some(path) = receiver
+        .await
+        .ok()
+        .flatten()
+        .and_then(|path| path.as_path().map(Path::to_path_buf))
+    else {
+        return Ok(None);
+    };
+
+    // 读取和写入日志文件都是阻塞操作，不占用异步运行时的线程
+    let handle = app_handle.clone();
+    let destination = path.clone();
+    tokio::task::spawn_blocking(move || -> Result<(), LoggingError> {
+        let content = recent_log_lines(&handle, max_lines)?.join("\n");
+        std::fs::write(&destination, content)?;
+        Ok(())
+    })
+    .await??;
+    log::info!("已导出最近 {} 行日志到 {:?}", max_lines, path);
+    Ok(Some(path))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::{Duration, SystemTime};
+
+    fn write_log(dir: &Path, name: &str, lines: &[&str], age_secs: u64) {
+        let path = dir.join(name);
+        std::fs::write(&path, lines.join("\n")).unwrap();
+        std::fs::File::options()
+            .write(true)
+            .open(&path)
+            .unwrap()
+            .set_modified(SystemTime::now() - Duration::from_secs(age_secs))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_recent_lines_span_rotated_files() {
+        let dir = std::env::temp_dir().join(format!("logging_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_log(&dir, "app_2024-11-01.log", &["一", "二", "三"], 120);
+        write_log(&dir, "app.log", &["四", "五"], 0);
+        write_log(&dir, "other.txt", &["六"], 0);
+
+        assert_eq!(recent_lines_in(&dir, 2).unwrap(), vec!["四", "五"]);
+        assert_eq!(
+            recent_lines_in(&dir, 4).unwrap(),
+            vec!["二", "三", "四", "五"]
+        );
+        assert_eq!(recent_lines_in(&dir, 10).unwrap().len(), 5);
+        assert!(recent_lines_in(&dir.join("missing"), 10)
+            .unwrap()
+            .is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    fn test_current_log_tail_is_bounded() {
+        let dir = std::env::temp_dir().join(format!("logging_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        write_log(&dir, "app_2024-11-01.log", &["旧"], 120);
+        write_log(&dir, "app.log", &["first", "second", "third"], 0);
+
+        assert_eq!(current_log_tail(&dir, 1024, 2), vec!["second", "third"]);
+        // 只读取末尾 11 字节，被截断的 "second" 不返回
+        assert_eq!(current_log_tail(&dir, 11, 10), vec!["third"]);
+        assert!(current_log_tail(&dir.join("missing"), 1024, 10).is_empty());
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+}
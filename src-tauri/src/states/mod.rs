@@ -1 +1,5 @@
+pub mod crash;
 pub mod data_center;
+pub mod diagnostics;
+pub mod logging;
+pub mod updater;
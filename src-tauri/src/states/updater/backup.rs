@@ -0,0 +1,179 @@
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::{AppHandle, Manager};
+
+const PENDING_UPDATE_FILE_NAME: &str = "pending_update.json";
+const BACKUP_DIR_NAME: &str = "backups";
+// 只保留最近的几份快照，避免占用过多磁盘空间
+const MAX_SNAPSHOTS: usize = 3;
+
+// 安装更新前写入的标记，新版本首次启动时据此校验数据库
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PendingUpdateMarker {
+    pub from_version: String,
+    pub to_version: String,
+    pub snapshot_path: PathBuf,
+    pub created_at: i64,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct DatabaseRecoveryStatus {
+    // 新版本首次启动时数据库打开或迁移失败
+    pub failed: bool,
+    pub error: Option<String>,
+    pub from_version: Option<String>,
+    // 是否有可用于恢复的快照
+    pub snapshot_available: bool,
+}
+
+fn pending_update_path(data_dir: &Path) -> PathBuf {
+    data_dir.join(PENDING_UPDATE_FILE_NAME)
+}
+
+// 复制数据库文件（以及可能存在的 WAL 文件）到 backups 目录，并写入待校验标记
+pub fn snapshot_database(
+    data_dir: &Path,
+    db_path: &Path,
+    from_version: &str,
+    to_version: &str,
+) -> std::io::Result<PendingUpdateMarker> {
+    let backup_dir = data_dir.join(BACKUP_DIR_NAME);
+    std::fs::create_dir_all(&backup_dir)?;
+
+    let created_at = chrono::Utc::now().timestamp();
+    let snapshot_path = backup_dir.join(format!("case_data-{}-{}.db", from_version, created_at));
+    if db_path.exists() {
+        std::fs::copy(db_path, &snapshot_path)?;
+    }
+    let db_wal_path = wal_path(db_path);
+    if db_wal_path.exists() {
+        std::fs::copy(&db_wal_path, wal_path(&snapshot_path))?;
+    }
+
+    let marker = PendingUpdateMarker {
+        from_version: from_version.to_string(),
+        to_version: to_version.to_string(),
+        snapshot_path,
+        created_at,
+    };
+    std::fs::write(
+        pending_update_path(data_dir),
+        serde_json::to_string_pretty(&marker)?,
+    )?;
+    prune_snapshots(&backup_dir)?;
+    Ok(marker)
+}
+
+fn wal_path(db_path: &Path) -> PathBuf {
+    let mut wal_path = db_path.as_os_str().to_owned();
+    wal_path.push(".wal");
+    PathBuf::from(wal_path)
+}
+
+fn prune_snapshots(backup_dir: &Path) -> std::io::Result<()> {
+    let mut snapshots: Vec<PathBuf> = std::fs::read_dir(backup_dir)?
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map(|ext| ext == "db").unwrap_or(false))
+        .collect();
+    if snapshots.len() <= MAX_SNAPSHOTS {
+        return Ok(());
+    }
+    // 文件名中带有时间戳，按修改时间排序即可
+    snapshots.sort_by_key(|path| {
+        std::fs::metadata(path)
+            .and_then(|metadata| metadata.modified())
+            .ok()
+    });
+    for path in &snapshots[..snapshots.len() - MAX_SNAPSHOTS] {
+        let _ = std::fs::remove_file(path);
+        let _ = std::fs::remove_file(wal_path(path));
+    }
+    Ok(())
+}
+
+pub struct DatabaseRecoveryState {
+    data_dir: PathBuf,
+    db_path: Option<PathBuf>,
+    marker: Option<PendingUpdateMarker>,
+    error: Mutex<Option<String>>,
+}
+
+impl DatabaseRecoveryState {
+    pub fn new(app_handle: &AppHandle, db_path: Option<PathBuf>) -> Result<Self, tauri::Error> {
+        let data_dir = app_handle.path().app_data_dir()?;
+        let marker = std::fs::read_to_string(pending_update_path(&data_dir))
+            .ok()
+            .and_then(|content| serde_json::from_str::<PendingUpdateMarker>(&content).ok());
+
+        Ok(Self {
+            data_dir,
+            db_path,
+            marker,
+            error: Mutex::new(None),
+        })
+    }
+
+    // 数据库打开和迁移成功后调用。已经运行在新版本上时，清除待校验标记
+    pub fn mark_verified(&self, current_version: &str) {
+        if let Some(marker) = &self.marker {
+            if marker.from_version != current_version {
+                let _ = std::fs::remove_file(pending_update_path(&self.data_dir));
+            }
+        }
+    }
+
+    pub fn mark_failed(&self, error: String) {
+        *self.error.lock().unwrap() = Some(error);
+    }
+
+    pub fn status(&self) -> DatabaseRecoveryStatus {
+        let error = self.error.lock().unwrap().clone();
+        DatabaseRecoveryStatus {
+            failed: error.is_some(),
+            error,
+            from_version: self
+                .marker
+                .as_ref()
+                .map(|marker| marker.from_version.clone()),
+            snapshot_available: self
+                .marker
+                .as_ref()
+                .map(|marker| marker.snapshot_path.exists())
+                .unwrap_or(false),
+        }
+    }
+
+    // 用更新前的快照覆盖当前数据库，调用方需要随后重启应用。
+    // 只有本次启动时数据库打开失败才允许恢复，此时数据库文件没有被占用；正常运行时覆盖会损坏正在使用的数据库
+    pub fn restore_snapshot(&self) -> std::io::Result<()> {
+        if self.error.lock().unwrap().is_none() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::PermissionDenied,
+                "数据库已正常打开，只有更新后数据库打开失败时才能恢复快照",
+            ));
+        }
+        let marker = self.marker.as_ref().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "没有可用的数据库快照")
+        })?;
+        let db_path = self.db_path.as_ref().ok_or_else(|| {
+            std::io::Error::new(std::io::ErrorKind::NotFound, "无法确定数据库路径")
+        })?;
+
+        if !marker.snapshot_path.exists() {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::NotFound,
+                format!("数据库快照不存在: {:?}", marker.snapshot_path),
+            ));
+        }
+        std::fs::copy(&marker.snapshot_path, db_path)?;
+        let snapshot_wal_path = wal_path(&marker.snapshot_path);
+        if snapshot_wal_path.exists() {
+            std::fs::copy(&snapshot_wal_path, wal_path(db_path))?;
+        } else {
+            let _ = std::fs::remove_file(wal_path(db_path));
+        }
+        std::fs::remove_file(pending_update_path(&self.data_dir))?;
+        Ok(())
+    }
+}
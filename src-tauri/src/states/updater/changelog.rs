@@ -0,0 +1,142 @@
+use semver::Version;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+// 打包进应用的版本历史，由 build.rs 从仓库根目录的 version.json 复制
+const VERSION_HISTORY: &str = include_str!(concat!(env!("OUT_DIR"), "/version.json"));
+
+const CATEGORIES: [&str; 4] = ["修复", "优化", "功能", "重构"];
+const OTHER_CATEGORY: &str = "其他";
+
+#[derive(Debug, Clone, Deserialize)]
+struct VersionRecord {
+    version: String,
+    notes: String,
+    time: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ChangelogEntry {
+    pub version: String,
+    pub time: String,
+    pub groups: BTreeMap<String, Vec<String>>,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct Changelog {
+    // 按版本从新到旧排列
+    pub entries: Vec<ChangelogEntry>,
+    // 所有版本的说明按类别合并
+    pub groups: BTreeMap<String, Vec<String>>,
+}
+
+// 每行形如 " - 修复：登录页面一直处于检查更新"，无法识别类别的归入“其他”
+fn parse_notes(notes: &str) -> BTreeMap<String, Vec<String>> {
+    let mut groups: BTreeMap<String, Vec<String>> = BTreeMap::new();
+    for line in notes.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let line = line.trim_start_matches(['-', '*']).trim();
+        let (category, content) = match line.split_once(['：', ':']) {
+            Some((category, content)) if CATEGORIES.contains(&category.trim()) => {
+                (category.trim(), content.trim())
+            }
+            _ => (OTHER_CATEGORY, line),
+        };
+        groups
+            .entry(category.to_string())
+            .or_default()
+            .push(content.to_string());
+    }
+    groups
+}
+
+fn load_history(source: &str) -> Vec<(Version, VersionRecord)> {
+    let records: Vec<VersionRecord> = serde_json::from_str(source).unwrap_or_default();
+    let mut history: Vec<(Version, VersionRecord)> = records
+        .into_iter()
+        .filter_map(|record| {
+            Version::parse(record.version.trim_start_matches('v'))
+                .ok()
+                .map(|version| (version, record))
+        })
+        .collect();
+    history.sort_by(|a, b| b.0.cmp(&a.0));
+    history
+}
+
+// 返回 (from, to] 区间内所有版本的更新说明
+pub fn changelog_between(from: &Version, to: &Version) -> Changelog {
+    build_changelog(VERSION_HISTORY, from, to)
+}
+
+fn build_changelog(source: &str, from: &Version, to: &Version) -> Changelog {
+    let mut changelog = Changelog::default();
+    for (version, record) in load_history(source) {
+        if version <= *from || version > *to {
+            continue;
+        }
+        let groups = parse_notes(&record.notes);
+        for (category, items) in &groups {
+            changelog
+                .groups
+                .entry(category.clone())
+                .or_default()
+                .extend(items.iter().cloned());
+        }
+        changelog.entries.push(ChangelogEntry {
+            version: record.version,
+            time: record.time,
+            groups,
+        });
+    }
+    changelog
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HISTORY_FIXTURE: &str = r##"[
+        {
+            "version": "0.2.2",
+            "notes": "# Version 0.2.2\n - 修复：登录页面一直处于检查更新\n - 优化：自动生成更新信息",
+            "time": "2024-12-20 12:00:00"
+        },
+        {
+            "version": "0.2.1",
+            "notes": "# Version 0.2.1\n - 重构：项目支出绩效重构\n - 优化：表格性能优化\n - 杂项：表格组件版本升级",
+            "time": "2024-12-19 12:00:00"
+        },
+        {
+            "version": "0.1.0",
+            "notes": "# Version 0.1.0\n - 初步完成app界面及预算计算案例库查询",
+            "time": "2024-12-16 12:00:00"
+        }
+    ]"##;
+
+    #[test]
+    fn test_changelog_between() {
+        let from = Version::parse("0.1.0").unwrap();
+        let to = Version::parse("0.2.2").unwrap();
+        let changelog = build_changelog(HISTORY_FIXTURE, &from, &to);
+
+        let versions: Vec<&str> = changelog
+            .entries
+            .iter()
+            .map(|entry| entry.version.as_str())
+            .collect();
+        assert_eq!(versions, vec!["0.2.2", "0.2.1"]);
+        assert_eq!(
+            changelog.groups["修复"],
+            vec!["登录页面一直处于检查更新".to_string()]
+        );
+        assert_eq!(changelog.groups["优化"].len(), 2);
+        assert_eq!(
+            changelog.groups["其他"],
+            vec!["杂项：表格组件版本升级".to_string()]
+        );
+    }
+}
@@ -0,0 +1,126 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+
+const STABLE_ENDPOINT: &str = "https://ghproxy.uk.arm.useforall.top/https://github.com/LimLLL/test_tauri_updater/releases/latest/download/latest.json";
+const BETA_ENDPOINT: &str = "https://ghproxy.uk.arm.useforall.top/https://github.com/LimLLL/test_tauri_updater/releases/download/beta/latest.json";
+const INTERNAL_ENDPOINT: &str = "https://ghproxy.uk.arm.useforall.top/https://github.com/LimLLL/test_tauri_updater/releases/download/internal/latest.json";
+
+const SETTINGS_FILE_NAME: &str = "update_settings.json";
+const DEFAULT_CHECK_INTERVAL_MINUTES: u64 = 240;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    #[default]
+    Stable,
+    Beta,
+    Internal,
+}
+
+impl UpdateChannel {
+    pub fn default_endpoints(&self) -> Vec<String> {
+        match self {
+            UpdateChannel::Stable => vec![STABLE_ENDPOINT.to_string()],
+            UpdateChannel::Beta => vec![BETA_ENDPOINT.to_string()],
+            UpdateChannel::Internal => vec![INTERNAL_ENDPOINT.to_string()],
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct UpdateSettings {
+    pub channel: UpdateChannel,
+    // 切换到版本号更低的渠道时允许降级安装的渠道，只对这一次切换有效：
+    // 降级安装完成或再次切换渠道后清除
+    pub downgrade_channel: Option<UpdateChannel>,
+    // 各渠道自定义的更新地址，未配置的渠道使用内置地址
+    pub endpoints: HashMap<UpdateChannel, Vec<String>>,
+    // 后台定时检查更新的间隔（分钟）
+    pub check_interval_minutes: u64,
+    // 免打扰时段内不弹出更新提示
+    pub quiet_hours: Option<QuietHours>,
+    // 用户选择“稍后提醒”后的推迟信息
+    pub deferral: Option<UpdateDeferral>,
+    // 上次展示“更新内容”时的版本，用于升级后只展示一次
+    pub last_seen_version: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct QuietHours {
+    // 本地时间的小时数，0-23；start 大于 end 时表示跨越午夜
+    pub start_hour: u32,
+    pub end_hour: u32,
+}
+
+impl QuietHours {
+    pub fn contains(&self, hour: u32) -> bool {
+        if self.start_hour <= self.end_hour {
+            hour >= self.start_hour && hour < self.end_hour
+        } else {
+            hour >= self.start_hour || hour < self.end_hour
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UpdateDeferral {
+    pub version: String,
+    // Unix 时间戳（秒）
+    pub until: i64,
+}
+
+impl Default for UpdateSettings {
+    fn default() -> Self {
+        Self {
+            channel: UpdateChannel::Stable,
+            downgrade_channel: None,
+            endpoints: HashMap::new(),
+            check_interval_minutes: DEFAULT_CHECK_INTERVAL_MINUTES,
+            quiet_hours: None,
+            deferral: None,
+            last_seen_version: None,
+        }
+    }
+}
+
+impl UpdateSettings {
+    pub fn file_path(config_dir: &Path) -> PathBuf {
+        config_dir.join(SETTINGS_FILE_NAME)
+    }
+
+    // 配置文件不存在或损坏时回退到默认配置，避免更新设置影响应用启动
+    pub fn load(path: &Path) -> Self {
+        std::fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str(&content).ok())
+            .unwrap_or_default()
+    }
+
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, content)
+    }
+
+    pub fn endpoints_for(&self, channel: UpdateChannel) -> Vec<String> {
+        match self.endpoints.get(&channel) {
+            Some(endpoints) if !endpoints.is_empty() => endpoints.clone(),
+            _ => channel.default_endpoints(),
+        }
+    }
+
+    pub fn allows_downgrade(&self) -> bool {
+        self.downgrade_channel == Some(self.channel)
+    }
+
+    pub fn is_deferred(&self, version: &str, now: i64) -> bool {
+        match &self.deferral {
+            Some(deferral) => deferral.version == version && deferral.until > now,
+            None => false,
+        }
+    }
+}
@@ -0,0 +1,362 @@
+use crate::states::data_center::performance_evaluation::case_data::database::{
+    CustomError, PerformanceEvaluationCaseDataState,
+};
+use crate::states::updater::backup::snapshot_database;
+use crate::states::updater::changelog::{changelog_between, Changelog};
+use crate::states::updater::channel::{QuietHours, UpdateChannel, UpdateDeferral, UpdateSettings};
+use crate::states::updater::offline::OfflineUpdatePackage;
+use crate::states::updater::rollout::{load_or_create_install_id, ManifestPolicy, RolloutDecision};
+use crate::update_server::server::{ServedFiles, UpdateFileServer, MANIFEST_FILE_NAME};
+use semver::Version;
+use serde::Serialize;
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use tauri::{AppHandle, Emitter, Manager, Url};
+use tauri_plugin_updater::{Update, UpdaterExt};
+use thiserror::Error;
+
+#[derive(Error, Debug)]
+pub enum UpdaterError {
+    #[error("Updater error: {0}")]
+    PluginError(#[from] tauri_plugin_updater::Error),
+    #[error("IO error: {0}")]
+    IoError(#[from] std::io::Error),
+    #[error("Invalid endpoint: {0}")]
+    InvalidEndpoint(String),
+    #[error("Tauri error: {0}")]
+    TauriError(#[from] tauri::Error),
+    #[error("Semver error: {0}")]
+    SemverError(#[from] semver::Error),
+    #[error("Database error: {0}")]
+    DatabaseError(#[from] CustomError),
+    #[error("Offline update error: {0}")]
+    OfflineUpdateError(String),
+    #[error("No pending update, please check for updates first")]
+    NoPendingUpdate,
+}
+
+// 命令返回的错误需要能序列化给前端
+impl Serialize for UpdaterError {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.to_string())
+    }
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct UpdateInfo {
+    pub channel: UpdateChannel,
+    pub current_version: String,
+    pub version: String,
+    pub notes: Option<String>,
+    pub date: Option<String>,
+    // 当前版本低于最低支持版本或已被列入黑名单时为 true
+    pub mandatory: bool,
+}
+
+pub struct UpdateManagerState {
+    app_handle: AppHandle,
+    settings_path: PathBuf,
+    settings: Mutex<UpdateSettings>,
+    install_id: String,
+    // check 之后暂存的更新，安装时直接使用，避免重复请求更新地址
+    pending_update: tokio::sync::Mutex<Option<Update>>,
+    // 修改检查计划时通知后台检查任务
+    schedule_changed: tokio::sync::Notify,
+}
+
+impl UpdateManagerState {
+    pub fn new(app_handle: &AppHandle) -> Result<Self, UpdaterError> {
+        let config_dir = app_handle.path().app_config_dir()?;
+        let settings_path = UpdateSettings::file_path(&config_dir);
+        let settings = UpdateSettings::load(&settings_path);
+        let data_dir = app_handle.path().app_data_dir()?;
+        // 数据目录不可写时使用本次运行的临时标识，只影响灰度分桶的稳定性
+        let install_id = load_or_create_install_id(&data_dir).unwrap_or_else(|err| {
+            log::warn!("无法保存安装标识，使用临时标识: {:?}", err);
+            uuid::Uuid::new_v4().to_string()
+        });
+
+        Ok(Self {
+            app_handle: app_handle.clone(),
+            settings_path,
+            settings: Mutex::new(settings),
+            install_id,
+            pending_update: tokio::sync::Mutex::new(None),
+            schedule_changed: tokio::sync::Notify::new(),
+        })
+    }
+
+    pub fn settings(&self) -> UpdateSettings {
+        self.settings.lock().unwrap().clone()
+    }
+
+    pub async fn set_channel(
+        &self,
+        channel: UpdateChannel,
+        allow_downgrade: bool,
+    ) -> Result<UpdateSettings, UpdaterError> {
+        let settings = {
+            let mut settings = self.settings.lock().unwrap();
+            settings.channel = channel;
+            settings.downgrade_channel = allow_downgrade.then_some(channel);
+            settings.save(&self.settings_path)?;
+            settings.clone()
+        };
+        // 切换渠道后之前检查到的更新不再有效
+        self.pending_update.lock().await.take();
+        Ok(settings)
+    }
+
+    pub fn set_channel_endpoints(
+        &self,
+        channel: UpdateChannel,
+        endpoints: Vec<String>,
+    ) -> Result<UpdateSettings, UpdaterError> {
+        for endpoint in &endpoints {
+            Url::parse(endpoint).map_err(|_| UpdaterError::InvalidEndpoint(endpoint.clone()))?;
+        }
+        let mut settings = self.settings.lock().unwrap();
+        if endpoints.is_empty() {
+            settings.endpoints.remove(&channel);
+        } else {
+            settings.endpoints.insert(channel, endpoints);
+        }
+        settings.save(&self.settings_path)?;
+        Ok(settings.clone())
+    }
+
+    pub fn set_schedule(
+        &self,
+        check_interval_minutes: u64,
+        quiet_hours: Option<QuietHours>,
+    ) -> Result<UpdateSettings, UpdaterError> {
+        let mut settings = self.settings.lock().unwrap();
+        // 间隔至少 1 分钟，避免频繁请求更新地址
+        settings.check_interval_minutes = check_interval_minutes.max(1);
+        settings.quiet_hours = quiet_hours;
+        settings.save(&self.settings_path)?;
+        self.schedule_changed.notify_one();
+        Ok(settings.clone())
+    }
+
+    // 等待检查计划被修改，供后台检查任务使用
+    pub async fn schedule_changed(&self) {
+        self.schedule_changed.notified().await
+    }
+
+    pub fn defer(&self, version: String, minutes: u64) -> Result<UpdateSettings, UpdaterError> {
+        let until = chrono::Utc::now().timestamp() + (minutes as i64) * 60;
+        let mut settings = self.settings.lock().unwrap();
+        settings.deferral = Some(UpdateDeferral { version, until });
+        settings.save(&self.settings_path)?;
+        Ok(settings.clone())
+    }
+
+    pub fn changelog_to(&self, target_version: &str) -> Result<Changelog, UpdaterError> {
+        let current_version = self.app_handle.package_info().version.clone();
+        let target_version = Version::parse(target_version.trim_start_matches('v'))?;
+        Ok(changelog_between(&current_version, &target_version))
+    }
+
+    // 升级后首次调用时返回上次展示版本到当前版本之间的更新内容，之后返回 None
+    pub fn take_whats_new(&self) -> Result<Option<Changelog>, UpdaterError> {
+        let current_version = self.app_handle.package_info().version.clone();
+        let mut settings = self.settings.lock().unwrap();
+        let last_seen_version = settings
+            .last_seen_version
+            .as_deref()
+            .and_then(|version| Version::parse(version).ok());
+
+        let whats_new = match last_seen_version {
+            Some(last_seen_version) if last_seen_version < current_version => {
+                Some(changelog_between(&last_seen_version, &current_version))
+            }
+            // 全新安装或版本未变化时不展示
+            _ => None,
+        };
+
+        if settings.last_seen_version.as_deref() != Some(current_version.to_string().as_str()) {
+            settings.last_seen_version = Some(current_version.to_string());
+            settings.save(&self.settings_path)?;
+        }
+        Ok(whats_new)
+    }
+
+    // 离线安装：先用配置中的 pubkey 校验签名，再通过本机临时更新服务走与在线更新相同的流程
+    pub async fn install_offline(
+        &self,
+        package: OfflineUpdatePackage,
+    ) -> Result<UpdateInfo, UpdaterError> {
+        let pubkey = self
+            .app_handle
+            .config()
+            .plugins
+            .0
+            .get("updater")
+            .and_then(|updater| updater["pubkey"].as_str())
+            .map(str::to_string)
+            .ok_or_else(|| UpdaterError::OfflineUpdateError("未配置更新公钥".to_string()))?;
+        let package = package
+            .verify(&pubkey)
+            .map_err(UpdaterError::OfflineUpdateError)?;
+
+        // 只监听本机回环地址，只提供校验过的更新包
+        let server = UpdateFileServer::start(
+            "127.0.0.1:0",
+            ServedFiles::Single {
+                file_name: package.bundle_file_name.clone(),
+                path: package.bundle_dir.join(&package.bundle_file_name),
+            },
+        )?;
+        server.set_manifest(package.manifest_for(&server.url(&package.bundle_file_name)));
+
+        let update = self
+            .build_updater(vec![server.url(MANIFEST_FILE_NAME)])?
+            .check()
+            .await?
+            .ok_or_else(|| {
+                UpdaterError::OfflineUpdateError(format!(
+                    "离线更新包版本 {} 不高于当前版本",
+                    package.manifest.version
+                ))
+            })?;
+        let info = UpdateInfo {
+            channel: self.settings().channel,
+            current_version: update.current_version.clone(),
+            version: update.version.clone(),
+            notes: update.body.clone(),
+            date: update.date.map(|date| date.to_string()),
+            mandatory: false,
+        };
+        // 直接安装校验过的离线更新，不经过 pending_update，避免被后台检查到的在线更新替换
+        self.install_update(&update).await?;
+        drop(server);
+        Ok(info)
+    }
+
+    // 安装更新前备份案例数据库，新版本启动失败时可以恢复
+    async fn snapshot_database(
+        &self,
+        from_version: &str,
+        to_version: &str,
+    ) -> Result<(), UpdaterError> {
+        if let Some(db) = self
+            .app_handle
+            .try_state::<PerformanceEvaluationCaseDataState>()
+        {
+            db.checkpoint().await?;
+        }
+        let db_path = PerformanceEvaluationCaseDataState::database_path(&self.app_handle)?;
+        let data_dir = self.app_handle.path().app_data_dir()?;
+        let marker = snapshot_database(&data_dir, &db_path, from_version, to_version)?;
+        log::info!("更新前数据库快照: {:?}", marker.snapshot_path);
+        Ok(())
+    }
+
+    fn build_updater(
+        &self,
+        endpoints: Vec<String>,
+    ) -> Result<tauri_plugin_updater::Updater, UpdaterError> {
+        let settings = self.settings();
+        let endpoints = endpoints
+            .iter()
+            .map(|endpoint| {
+                Url::parse(endpoint).map_err(|_| UpdaterError::InvalidEndpoint(endpoint.clone()))
+            })
+            .collect::<Result<Vec<Url>, UpdaterError>>()?;
+        let allow_downgrade = settings.allows_downgrade();
+
+        let updater = self
+            .app_handle
+            .updater_builder()
+            .endpoints(endpoints)?
+            .version_comparator(move |current, remote| {
+                // 默认只接受更高的版本；允许降级时，只要版本不同就视为可更新
+                if allow_downgrade {
+                    remote.version != current
+                } else {
+                    remote.version > current
+                }
+            })
+            .build()?;
+        Ok(updater)
+    }
+
+    pub async fn check(&self) -> Result<Option<UpdateInfo>, UpdaterError> {
+        let settings = self.settings();
+        let channel = settings.channel;
+        let endpoints = settings.endpoints_for(channel);
+        let update = match self.build_updater(endpoints)?.check().await? {
+            Some(update) => update,
+            None => {
+                self.pending_update.lock().await.take();
+                return Ok(None);
+            }
+        };
+
+        // 根据更新清单中的灰度比例、最低支持版本和黑名单决定是否提供此次更新
+        let policy = ManifestPolicy::from_manifest(&update.raw_json);
+        let current_version = Version::parse(&update.current_version)?;
+        let remote_version = Version::parse(&update.version)?;
+        let mandatory = match policy.evaluate(&self.install_id, &current_version, &remote_version) {
+            RolloutDecision::Skip => {
+                log::info!("更新检查 - 版本 {} 未对当前安装开放", update.version);
+                self.pending_update.lock().await.take();
+                return Ok(None);
+            }
+            RolloutDecision::Optional => false,
+            RolloutDecision::Mandatory => true,
+        };
+
+        let info = UpdateInfo {
+            channel,
+            current_version: update.current_version.clone(),
+            version: update.version.clone(),
+            notes: update.body.clone(),
+            date: update.date.map(|date| date.to_string()),
+            mandatory,
+        };
+        *self.pending_update.lock().await = Some(update);
+        Ok(Some(info))
+    }
+
+    pub async fn download_and_install(&self) -> Result<(), UpdaterError> {
+        let pending_update = self.pending_update.lock().await;
+        let update = pending_update
+            .as_ref()
+            .ok_or(UpdaterError::NoPendingUpdate)?;
+        self.install_update(update).await
+    }
+
+    async fn install_update(&self, update: &Update) -> Result<(), UpdaterError> {
+        self.snapshot_database(&update.current_version, &update.version)
+            .await?;
+
+        let app_handle = self.app_handle.clone();
+        let mut downloaded: usize = 0;
+        update
+            .download_and_install(
+                |chunk_length, content_length| {
+                    downloaded += chunk_length;
+                    let _ = app_handle.emit(
+                        "update_download_progress",
+                        json!({"downloaded": downloaded, "content_length": content_length}),
+                    );
+                },
+                || {
+                    let _ = app_handle.emit("update_download_finished", ());
+                },
+            )
+            .await?;
+
+        let mut settings = self.settings.lock().unwrap();
+        settings.deferral = None;
+        settings.downgrade_channel = None;
+        settings.save(&self.settings_path)?;
+        Ok(())
+    }
+}
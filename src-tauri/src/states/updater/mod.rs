@@ -0,0 +1,7 @@
+pub mod backup;
+pub mod changelog;
+pub mod channel;
+pub mod manager;
+pub mod offline;
+pub mod rollout;
+pub mod scheduler;
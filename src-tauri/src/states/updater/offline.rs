@@ -0,0 +1,101 @@
+use base64::Engine;
+use minisign_verify::{PublicKey, Signature};
+use serde::Deserialize;
+use serde_json::json;
+use std::path::{Path, PathBuf};
+
+// 离线更新包：安装包本体、tauri signer 生成的 .sig 文件和描述版本信息的清单
+#[derive(Debug, Clone, Deserialize)]
+pub struct OfflineUpdatePackage {
+    pub bundle_path: PathBuf,
+    pub signature_path: PathBuf,
+    pub manifest_path: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct OfflineManifest {
+    pub version: String,
+    #[serde(default)]
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub pub_date: Option<String>,
+}
+
+pub struct VerifiedOfflinePackage {
+    pub manifest: OfflineManifest,
+    pub bundle_dir: PathBuf,
+    pub bundle_file_name: String,
+    pub signature: String,
+}
+
+impl VerifiedOfflinePackage {
+    // 生成指向本地更新服务的清单，使离线安装与在线更新走同一套下载、校验、安装流程
+    pub fn manifest_for(&self, bundle_url: &str) -> Vec<u8> {
+        let mut manifest = json!({
+            "version": self.manifest.version,
+            "notes": self.manifest.notes,
+            "url": bundle_url,
+            "signature": self.signature,
+        });
+        if let Some(pub_date) = &self.manifest.pub_date {
+            manifest["pub_date"] = json!(pub_date);
+        }
+        serde_json::to_vec(&manifest).unwrap()
+    }
+}
+
+impl OfflineUpdatePackage {
+    pub fn verify(&self, pubkey: &str) -> Result<VerifiedOfflinePackage, String> {
+        let manifest = std::fs::read_to_string(&self.manifest_path)
+            .map_err(|err| format!("读取更新清单失败: {}", err))?;
+        let manifest: OfflineManifest =
+            serde_json::from_str(&manifest).map_err(|err| format!("更新清单格式错误: {}", err))?;
+
+        let signature = std::fs::read_to_string(&self.signature_path)
+            .map_err(|err| format!("读取签名文件失败: {}", err))?;
+        let signature = signature.trim().to_string();
+        let bundle =
+            std::fs::read(&self.bundle_path).map_err(|err| format!("读取更新包失败: {}", err))?;
+        verify_signature(&bundle, &signature, pubkey)?;
+
+        let bundle_file_name = file_name(&self.bundle_path)?;
+        let bundle_dir = self
+            .bundle_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default();
+
+        Ok(VerifiedOfflinePackage {
+            manifest,
+            bundle_dir,
+            bundle_file_name,
+            signature,
+        })
+    }
+}
+
+fn file_name(path: &Path) -> Result<String, String> {
+    path.file_name()
+        .and_then(|name| name.to_str())
+        .map(str::to_string)
+        .ok_or_else(|| format!("无效的更新包路径: {:?}", path))
+}
+
+// pubkey 和签名都是 base64 编码后的 minisign 文本，与 tauri.conf.json 中的配置一致
+pub fn verify_signature(data: &[u8], signature: &str, pubkey: &str) -> Result<(), String> {
+    let pubkey = decode_base64(pubkey)?;
+    let public_key = PublicKey::decode(&pubkey).map_err(|err| format!("公钥格式错误: {}", err))?;
+    let signature = decode_base64(signature)?;
+    let signature =
+        Signature::decode(&signature).map_err(|err| format!("签名格式错误: {}", err))?;
+    public_key
+        .verify(data, &signature, true)
+        .map_err(|err| format!("签名校验失败: {}", err))
+}
+
+fn decode_base64(value: &str) -> Result<String, String> {
+    let decoded = base64::engine::general_purpose::STANDARD
+        .decode(value.trim())
+        .map_err(|err| format!("base64 解码失败: {}", err))?;
+    String::from_utf8(decoded).map_err(|err| format!("签名内容不是有效的 UTF-8: {}", err))
+}
@@ -0,0 +1,174 @@
+use semver::Version;
+use serde::de::DeserializeOwned;
+use serde_json::Value as JsonValue;
+use sha2::{Digest, Sha256};
+use std::path::Path;
+
+const INSTALL_ID_FILE_NAME: &str = "install_id";
+
+// latest.json 中除 Tauri 标准字段外的发布控制字段
+#[derive(Debug, Clone, Default)]
+pub struct ManifestPolicy {
+    // 灰度比例，0-100，未填写时视为全量发布
+    pub rollout_percentage: Option<u8>,
+    // 低于该版本的安装必须更新，不受灰度和推迟提醒影响
+    pub min_supported_version: Option<String>,
+    // 有问题的版本，不会被安装；当前版本在其中时强制更新
+    pub blocked_versions: Vec<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RolloutDecision {
+    Skip,
+    Optional,
+    Mandatory,
+}
+
+impl ManifestPolicy {
+    // 各字段分别解析，某个字段格式错误时只忽略该字段，不影响黑名单和最低支持版本
+    pub fn from_manifest(raw_json: &JsonValue) -> Self {
+        Self {
+            rollout_percentage: manifest_field(raw_json, "rollout_percentage"),
+            min_supported_version: manifest_field(raw_json, "min_supported_version"),
+            blocked_versions: manifest_field::<Vec<JsonValue>>(raw_json, "blocked_versions")
+                .unwrap_or_default()
+                .into_iter()
+                .filter_map(|version| match version {
+                    JsonValue::String(version) => Some(version),
+                    other => {
+                        log::warn!("更新清单 blocked_versions 中的版本格式错误: {}", other);
+                        None
+                    }
+                })
+                .collect(),
+        }
+    }
+
+    pub fn evaluate(
+        &self,
+        install_id: &str,
+        current: &Version,
+        remote: &Version,
+    ) -> RolloutDecision {
+        if self.is_blocked(remote) {
+            return RolloutDecision::Skip;
+        }
+
+        let below_minimum = self
+            .min_supported_version
+            .as_deref()
+            .and_then(|version| Version::parse(version.trim_start_matches('v')).ok())
+            .map(|minimum| *current < minimum)
+            .unwrap_or(false);
+        if below_minimum || self.is_blocked(current) {
+            return RolloutDecision::Mandatory;
+        }
+
+        let percentage = self.rollout_percentage.unwrap_or(100).min(100);
+        if rollout_bucket(install_id, remote) < percentage {
+            RolloutDecision::Optional
+        } else {
+            RolloutDecision::Skip
+        }
+    }
+
+    fn is_blocked(&self, version: &Version) -> bool {
+        self.blocked_versions
+            .iter()
+            .filter_map(|blocked| Version::parse(blocked.trim_start_matches('v')).ok())
+            .any(|blocked| blocked == *version)
+    }
+}
+
+fn manifest_field<T: DeserializeOwned>(raw_json: &JsonValue, name: &str) -> Option<T> {
+    let value = raw_json.get(name).filter(|value| !value.is_null())?;
+    match serde_json::from_value(value.clone()) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            log::warn!("更新清单字段 {} 格式错误: {}", name, err);
+            None
+        }
+    }
+}
+
+// 同一个安装对同一个版本始终落在同一个桶里，提高灰度比例时已命中的安装不会退出
+pub fn rollout_bucket(install_id: &str, version: &Version) -> u8 {
+    let digest = Sha256::digest(format!("{}:{}", install_id, version).as_bytes());
+    let value = u32::from_be_bytes([digest[0], digest[1], digest[2], digest[3]]);
+    (value % 100) as u8
+}
+
+// 每个安装的唯一标识，首次读取时生成并写入应用数据目录
+pub fn load_or_create_install_id(data_dir: &Path) -> std::io::Result<String> {
+    let path = data_dir.join(INSTALL_ID_FILE_NAME);
+    if let Ok(install_id) = std::fs::read_to_string(&path) {
+        let install_id = install_id.trim();
+        if !install_id.is_empty() {
+            return Ok(install_id.to_string());
+        }
+    }
+
+    std::fs::create_dir_all(data_dir)?;
+    let install_id = uuid::Uuid::new_v4().to_string();
+    std::fs::write(&path, &install_id)?;
+    Ok(install_id)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_rollout_bucket_is_stable() {
+        let version = Version::parse("0.8.0").unwrap();
+        let bucket = rollout_bucket("install-a", &version);
+        assert_eq!(bucket, rollout_bucket("install-a", &version));
+        assert!(bucket < 100);
+    }
+
+    #[test]
+    fn test_evaluate_manifest_policy() {
+        let current = Version::parse("0.7.0").unwrap();
+        let remote = Version::parse("0.8.0").unwrap();
+
+        let policy = ManifestPolicy::from_manifest(&json!({"blocked_versions": ["0.8.0"]}));
+        assert_eq!(
+            policy.evaluate("id", &current, &remote),
+            RolloutDecision::Skip
+        );
+
+        let policy = ManifestPolicy::from_manifest(&json!({
+            "rollout_percentage": 0,
+            "min_supported_version": "0.7.5"
+        }));
+        assert_eq!(
+            policy.evaluate("id", &current, &remote),
+            RolloutDecision::Mandatory
+        );
+
+        let policy = ManifestPolicy::from_manifest(&json!({"rollout_percentage": 0}));
+        assert_eq!(
+            policy.evaluate("id", &current, &remote),
+            RolloutDecision::Skip
+        );
+
+        // 灰度比例格式错误时仍然按黑名单和最低支持版本处理
+        let policy = ManifestPolicy::from_manifest(&json!({
+            "rollout_percentage": "50%",
+            "min_supported_version": "v0.7.5",
+            "blocked_versions": ["0.6.0", 7]
+        }));
+        assert_eq!(policy.blocked_versions, vec!["0.6.0".to_string()]);
+        assert_eq!(
+            policy.evaluate("id", &current, &remote),
+            RolloutDecision::Mandatory
+        );
+
+        let policy = ManifestPolicy::from_manifest(&json!({"version": "0.8.0"}));
+        assert_eq!(
+            policy.evaluate("id", &current, &remote),
+            RolloutDecision::Optional
+        );
+    }
+}
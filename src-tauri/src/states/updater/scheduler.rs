@@ -0,0 +1,55 @@
+use crate::states::updater::manager::UpdateManagerState;
+use chrono::{Local, Timelike, Utc};
+use serde_json::json;
+use std::time::Duration;
+use tauri::{AppHandle, Emitter, Manager};
+
+// 启动时立即检查一次，之后按照设置中的间隔定时检查，修改检查间隔后立即按新的设置重新开始计时。
+// 免打扰时段和“稍后提醒”期间照常检查，只跳过提示。
+pub fn spawn_update_scheduler(app_handle: &AppHandle) {
+    let app_handle = app_handle.clone();
+    tauri::async_runtime::spawn(async move {
+        loop {
+            let manager = app_handle.state::<UpdateManagerState>();
+            let settings = manager.settings();
+            let in_quiet_hours = settings
+                .quiet_hours
+                .map(|quiet_hours| quiet_hours.contains(Local::now().hour()))
+                .unwrap_or(false);
+
+            match manager.check().await {
+                Ok(Some(info)) => {
+                    if in_quiet_hours {
+                        log::debug!("后台更新检查 - 处于免打扰时段，不提示版本 {}", info.version);
+                    // 强制更新不受“稍后提醒”影响
+                    } else if !info.mandatory
+                        && settings.is_deferred(&info.version, Utc::now().timestamp())
+                    {
+                        log::info!("后台更新检查 - 版本 {} 已推迟提醒", info.version);
+                    } else {
+                        let _ = app_handle.emit(
+                            "update_available",
+                            json!({
+                                "version": info.version,
+                                "notes": info.notes,
+                                "mandatory": info.mandatory
+                            }),
+                        );
+                    }
+                }
+                Ok(None) => {}
+                Err(err) => log::warn!("后台更新检查失败: {:?}", err),
+            }
+
+            let mut interval = Duration::from_secs(settings.check_interval_minutes.max(1) * 60);
+            // 等待到下一次检查，期间修改了检查计划则按新的间隔重新计时
+            while tokio::time::timeout(interval, manager.schedule_changed())
+                .await
+                .is_ok()
+            {
+                interval =
+                    Duration::from_secs(manager.settings().check_interval_minutes.max(1) * 60);
+            }
+        }
+    });
+}
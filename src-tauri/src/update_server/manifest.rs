@@ -0,0 +1,299 @@
+use chrono::{Local, NaiveDateTime};
+use serde::Deserialize;
+use serde_json::{json, Map, Value as JsonValue};
+use std::path::Path;
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct VersionRecord {
+    pub version: String,
+    pub notes: String,
+    pub time: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UpdateArtifact {
+    // Tauri 更新清单中的平台标识，例如 windows-x86_64
+    pub target: String,
+    // 安装包类型，例如 nsis、msi、app、appimage
+    pub installer: &'static str,
+    pub file_name: String,
+    pub signature: String,
+}
+
+// 安装包后缀与平台、安装包类型的对应关系，顺序决定同一平台的默认安装包
+const ARTIFACT_KINDS: &[(&str, &str, &str)] = &[
+    ("-setup.exe", "windows", "nsis"),
+    (".msi", "windows", "msi"),
+    (".app.tar.gz", "darwin", "app"),
+    (".AppImage", "linux", "appimage"),
+    (".deb", "linux", "deb"),
+    (".rpm", "linux", "rpm"),
+];
+
+pub fn latest_version_record(version_json: &Path) -> Result<VersionRecord, String> {
+    let content = std::fs::read_to_string(version_json)
+        .map_err(|err| format!("读取 {:?} 失败: {}", version_json, err))?;
+    let records: Vec<VersionRecord> =
+        serde_json::from_str(&content).map_err(|err| format!("version.json 格式错误: {}", err))?;
+    records
+        .into_iter()
+        .max_by(|a, b| {
+            let a = semver::Version::parse(&a.version).ok();
+            let b = semver::Version::parse(&b.version).ok();
+            a.cmp(&b)
+        })
+        .ok_or_else(|| "version.json 中没有版本记录".to_string())
+}
+
+fn artifact_arch(file_name: &str, os: &str) -> &'static str {
+    let lower = file_name.to_lowercase();
+    if lower.contains("aarch64") || lower.contains("arm64") {
+        "aarch64"
+    } else if lower.contains("i686") || lower.contains("_x86.") || lower.contains("_x86_") {
+        "i686"
+    } else if os == "darwin" && !lower.contains("x64") && !lower.contains("x86_64") {
+        // macOS 构建目前只发布 Apple Silicon 版本
+        "aarch64"
+    } else {
+        "x86_64"
+    }
+}
+
+// 扫描目录中带有 .sig 签名文件的安装包
+pub fn scan_artifacts(dir: &Path) -> Result<Vec<UpdateArtifact>, String> {
+    let entries =
+        std::fs::read_dir(dir).map_err(|err| format!("读取目录 {:?} 失败: {}", dir, err))?;
+    let mut artifacts = Vec::new();
+    for entry in entries.flatten() {
+        let path = entry.path();
+        let Some(file_name) = path.file_name().and_then(|name| name.to_str()) else {
+            continue;
+        };
+        let Some((_, os, installer)) = ARTIFACT_KINDS
+            .iter()
+            .find(|(suffix, _, _)| file_name.ends_with(suffix))
+        else {
+            continue;
+        };
+
+        let signature_path = dir.join(format!("{}.sig", file_name));
+        let Ok(signature) = std::fs::read_to_string(&signature_path) else {
+            log::warn!("跳过没有签名文件的安装包: {}", file_name);
+            continue;
+        };
+        artifacts.push(UpdateArtifact {
+            target: format!("{}-{}", os, artifact_arch(file_name, os)),
+            installer,
+            file_name: file_name.to_string(),
+            signature: signature.trim().to_string(),
+        });
+    }
+    artifacts.sort_by(|a, b| a.file_name.cmp(&b.file_name));
+    Ok(artifacts)
+}
+
+fn installer_rank(installer: &str) -> usize {
+    ARTIFACT_KINDS
+        .iter()
+        .position(|(_, _, kind)| *kind == installer)
+        .unwrap_or(usize::MAX)
+}
+
+// 安装包文件名中的版本号是否与清单版本一致，文件名中没有版本号时返回 None。
+// 只识别 `主.次.修订` 形式的数字片段，`x86_64` 之类的架构名不会被误认为版本号。
+fn artifact_version_matches(file_name: &str, version: &semver::Version) -> Option<bool> {
+    let versions: Vec<&str> = file_name
+        .split(|c: char| !c.is_ascii_digit() && c != '.')
+        .map(|token| token.trim_matches('.'))
+        .filter(|token| {
+            let parts: Vec<&str> = token.split('.').collect();
+            parts.len() == 3 && parts.iter().all(|part| !part.is_empty())
+        })
+        .collect();
+    if versions.is_empty() {
+        return None;
+    }
+    let core = format!("{}.{}.{}", version.major, version.minor, version.patch);
+    Some(
+        versions.contains(&core.as_str())
+            && (version.pre.is_empty() || file_name.contains(&version.to_string())),
+    )
+}
+
+// 生成 Tauri 静态格式的 latest.json。
+// 每个安装包写入 `{os}-{arch}-{installer}`，同一平台优先级最高的安装包同时写入 `{os}-{arch}`。
+// 产物目录中混有其他版本的安装包时拒绝生成，避免客户端下载到与清单版本不符的安装包。
+pub fn generate_manifest(
+    record: &VersionRecord,
+    artifacts: &[UpdateArtifact],
+    base_url: &str,
+) -> Result<JsonValue, String> {
+    let version = semver::Version::parse(&record.version)
+        .map_err(|err| format!("版本号 {} 格式错误: {}", record.version, err))?;
+    let mismatched: Vec<&str> = artifacts
+        .iter()
+        .filter_map(
+            |artifact| match artifact_version_matches(&artifact.file_name, &version) {
+                Some(true) => None,
+                Some(false) => Some(artifact.file_name.as_str()),
+                None => {
+                    log::warn!(
+                        "安装包文件名中没有版本号，无法确认是否为 {}: {}",
+                        record.version,
+                        artifact.file_name
+                    );
+                    None
+                }
+            },
+        )
+        .collect();
+    if !mismatched.is_empty() {
+        return Err(format!(
+            "以下安装包与版本 {} 不一致: {}",
+            record.version,
+            mismatched.join(", ")
+        ));
+    }
+
+    let base_url = base_url.trim_end_matches('/');
+    let mut platforms = Map::new();
+    let mut default_ranks: Vec<(String, usize)> = Vec::new();
+
+    for artifact in artifacts {
+        let platform = json!({
+            "url": format!("{}/{}", base_url, percent_encode(&artifact.file_name)),
+            "signature": artifact.signature,
+        });
+        platforms.insert(
+            format!("{}-{}", artifact.target, artifact.installer),
+            platform.clone(),
+        );
+
+        let rank = installer_rank(artifact.installer);
+        match default_ranks
+            .iter_mut()
+            .find(|(target, _)| *target == artifact.target)
+        {
+            Some((_, current_rank)) if *current_rank <= rank => {}
+            Some((_, current_rank)) => {
+                *current_rank = rank;
+                platforms.insert(artifact.target.clone(), platform);
+            }
+            None => {
+                default_ranks.push((artifact.target.clone(), rank));
+                platforms.insert(artifact.target.clone(), platform);
+            }
+        }
+    }
+
+    Ok(json!({
+        "version": record.version,
+        "notes": record.notes,
+        "pub_date": pub_date(&record.time),
+        "platforms": platforms,
+    }))
+}
+
+// version.json 中的时间是本地时间，清单要求 RFC 3339 格式
+fn pub_date(time: &str) -> Option<String> {
+    NaiveDateTime::parse_from_str(time, "%Y-%m-%d %H:%M:%S")
+        .ok()
+        .and_then(|time| time.and_local_timezone(Local).single())
+        .map(|time| time.to_rfc3339())
+}
+
+fn percent_encode(input: &str) -> String {
+    let mut encoded = String::with_capacity(input.len());
+    for byte in input.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_artifact_version_matches() {
+        let version = semver::Version::parse("0.8.0").unwrap();
+        assert_eq!(
+            artifact_version_matches("测试_0.8.0_x64-setup.exe", &version),
+            Some(true)
+        );
+        assert_eq!(
+            artifact_version_matches("测试-0.8.0-1.x86_64.rpm", &version),
+            Some(true)
+        );
+        assert_eq!(
+            artifact_version_matches("测试_0.7.2_x64_zh-CN.msi", &version),
+            Some(false)
+        );
+        assert_eq!(
+            artifact_version_matches("测试_aarch64.app.tar.gz", &version),
+            None
+        );
+
+        let version = semver::Version::parse("0.9.0-beta.1").unwrap();
+        assert_eq!(
+            artifact_version_matches("测试_0.9.0-beta.1_amd64.AppImage", &version),
+            Some(true)
+        );
+        assert_eq!(
+            artifact_version_matches("测试_0.9.0_amd64.AppImage", &version),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_generate_manifest() {
+        let dir = std::env::temp_dir().join(format!("update_manifest_test_{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        for (file_name, signature) in [
+            ("测试_0.8.0_x64-setup.exe", "nsis-signature"),
+            ("测试_0.8.0_x64_zh-CN.msi", "msi-signature"),
+            ("测试_aarch64.app.tar.gz", "app-signature"),
+        ] {
+            std::fs::write(dir.join(file_name), b"bundle").unwrap();
+            std::fs::write(dir.join(format!("{}.sig", file_name)), signature).unwrap();
+        }
+        std::fs::write(dir.join("unsigned.AppImage"), b"bundle").unwrap();
+
+        let artifacts = scan_artifacts(&dir).unwrap();
+        assert_eq!(artifacts.len(), 3);
+
+        let record = VersionRecord {
+            version: "0.8.0".to_string(),
+            notes: "# Version 0.8.0".to_string(),
+            time: "2024-12-20 12:00:00".to_string(),
+        };
+        let manifest = generate_manifest(&record, &artifacts, "http://192.168.1.10:8080/").unwrap();
+        let platforms = &manifest["platforms"];
+        assert_eq!(platforms["windows-x86_64"]["signature"], "nsis-signature");
+        assert_eq!(
+            platforms["windows-x86_64-msi"]["signature"],
+            "msi-signature"
+        );
+        assert_eq!(platforms["darwin-aarch64"]["signature"], "app-signature");
+        assert!(platforms["windows-x86_64"]["url"]
+            .as_str()
+            .unwrap()
+            .starts_with("http://192.168.1.10:8080/%E6%B5%8B%E8%AF%95"));
+        assert!(manifest["pub_date"].is_string());
+
+        let record = VersionRecord {
+            version: "0.9.0".to_string(),
+            ..record
+        };
+        let err = generate_manifest(&record, &artifacts, "http://192.168.1.10:8080/").unwrap_err();
+        assert!(err.contains("测试_0.8.0_x64-setup.exe"));
+        assert!(!err.contains("测试_aarch64.app.tar.gz"));
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+}
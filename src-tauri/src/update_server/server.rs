@@ -0,0 +1,280 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::path::{Component, Path, PathBuf};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, RwLock};
+use std::thread::JoinHandle;
+use std::time::Duration;
+
+pub const MANIFEST_FILE_NAME: &str = "latest.json";
+
+// 更新服务可以下载的文件
+#[derive(Debug, Clone)]
+pub enum ServedFiles {
+    // 目录下的所有文件，局域网更新服务器使用
+    Directory(PathBuf),
+    // 只提供一个文件，离线安装时只暴露校验过的更新包
+    Single { file_name: String, path: PathBuf },
+}
+
+impl ServedFiles {
+    fn resolve(&self, relative_path: &str) -> Option<PathBuf> {
+        match self {
+            ServedFiles::Directory(root) => resolve_path(root, relative_path),
+            ServedFiles::Single { file_name, path } => {
+                (relative_path == file_name).then(|| path.clone())
+            }
+        }
+    }
+}
+
+// 提供更新清单和安装包下载的简易 HTTP 服务，局域网更新服务器和离线安装共用。
+// 清单可以通过 set_manifest 由内存提供，其余路径按 ServedFiles 映射到本地文件。
+pub struct UpdateFileServer {
+    addr: SocketAddr,
+    manifest: Arc<RwLock<Option<Vec<u8>>>>,
+    stopped: Arc<AtomicBool>,
+    worker: Option<JoinHandle<()>>,
+}
+
+impl UpdateFileServer {
+    pub fn start(bind_addr: &str, files: ServedFiles) -> std::io::Result<Self> {
+        let listener = TcpListener::bind(bind_addr)?;
+        let addr = listener.local_addr()?;
+        let manifest: Arc<RwLock<Option<Vec<u8>>>> = Arc::new(RwLock::new(None));
+        let stopped = Arc::new(AtomicBool::new(false));
+
+        let worker_manifest = Arc::clone(&manifest);
+        let worker_stopped = Arc::clone(&stopped);
+        let files = Arc::new(files);
+        let worker = std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if worker_stopped.load(Ordering::SeqCst) {
+                    break;
+                }
+                let stream = match stream {
+                    Ok(stream) => stream,
+                    Err(err) => {
+                        log::warn!("更新服务 - 接受连接失败: {:?}", err);
+                        continue;
+                    }
+                };
+                // 安装包较大，每个连接单独处理，避免多个客户端同时下载时互相阻塞
+                let manifest = worker_manifest.read().unwrap().clone();
+                let files = Arc::clone(&files);
+                std::thread::spawn(move || {
+                    if let Err(err) = handle_request(stream, &files, manifest.as_deref()) {
+                        log::warn!("更新服务 - 响应请求失败: {:?}", err);
+                    }
+                });
+            }
+        });
+
+        Ok(Self {
+            addr,
+            manifest,
+            stopped,
+            worker: Some(worker),
+        })
+    }
+
+    // 清单中的下载地址依赖监听端口，所以在启动之后再设置
+    pub fn set_manifest(&self, manifest: Vec<u8>) {
+        *self.manifest.write().unwrap() = Some(manifest);
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    // 阻塞当前线程直到服务停止，独立运行的更新服务器使用
+    pub fn wait(mut self) {
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+
+    pub fn url(&self, path: &str) -> String {
+        format!("http://{}/{}", self.addr, path.trim_start_matches('/'))
+    }
+}
+
+impl Drop for UpdateFileServer {
+    fn drop(&mut self) {
+        self.stopped.store(true, Ordering::SeqCst);
+        // 连接一次使 accept 返回，工作线程检查到停止标记后退出。
+        // 监听 0.0.0.0 时不能直接连接该地址，改为连接本机回环地址
+        let mut addr = self.addr;
+        if addr.ip().is_unspecified() {
+            addr.set_ip(std::net::Ipv4Addr::LOCALHOST.into());
+        }
+        let _ = TcpStream::connect(addr);
+        if let Some(worker) = self.worker.take() {
+            let _ = worker.join();
+        }
+    }
+}
+
+fn handle_request(
+    mut stream: TcpStream,
+    files: &ServedFiles,
+    manifest: Option<&[u8]>,
+) -> std::io::Result<()> {
+    stream.set_read_timeout(Some(Duration::from_secs(10)))?;
+    let mut reader = BufReader::new(stream.try_clone()?);
+    let mut request_line = String::new();
+    reader.read_line(&mut request_line)?;
+    // 读完请求头，请求体不需要处理
+    loop {
+        let mut line = String::new();
+        if reader.read_line(&mut line)? == 0 || line.trim().is_empty() {
+            break;
+        }
+    }
+
+    let url_path = request_line
+        .split_whitespace()
+        .nth(1)
+        .unwrap_or("")
+        .split('?')
+        .next()
+        .unwrap_or("");
+    let relative_path = percent_decode(url_path.trim_start_matches('/'));
+
+    if relative_path == MANIFEST_FILE_NAME {
+        if let Some(manifest) = manifest {
+            return respond(
+                &mut stream,
+                "200 OK",
+                "application/json",
+                manifest.len() as u64,
+                manifest,
+            );
+        }
+    }
+
+    match files.resolve(&relative_path) {
+        Some(path) if path.is_file() => {
+            let file = File::open(&path)?;
+            let length = file.metadata()?.len();
+            let content_type = if relative_path.ends_with(".json") {
+                "application/json"
+            } else {
+                "application/octet-stream"
+            };
+            respond(&mut stream, "200 OK", content_type, length, file)
+        }
+        _ => respond(
+            &mut stream,
+            "404 Not Found",
+            "text/plain",
+            9,
+            &b"Not Found"[..],
+        ),
+    }
+}
+
+fn respond(
+    stream: &mut TcpStream,
+    status: &str,
+    content_type: &str,
+    content_length: u64,
+    mut body: impl Read,
+) -> std::io::Result<()> {
+    write!(
+        stream,
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        status, content_type, content_length
+    )?;
+    std::io::copy(&mut body, stream)?;
+    stream.flush()
+}
+
+// 只允许访问 root 目录下的文件，拒绝 `..` 和绝对路径
+fn resolve_path(root: &Path, relative_path: &str) -> Option<PathBuf> {
+    let relative_path = Path::new(relative_path);
+    if relative_path
+        .components()
+        .any(|component| !matches!(component, Component::Normal(_)))
+    {
+        return None;
+    }
+    Some(root.join(relative_path))
+}
+
+// 安装包文件名可能包含中文或空格，请求路径中是百分号编码后的形式
+fn percent_decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        // %XX 需要完整的两位十六进制，末尾的 %XX 同样解码
+        if bytes[index] == b'%' && index + 3 <= bytes.len() {
+            let hex = std::str::from_utf8(&bytes[index + 1..index + 3]).ok();
+            if let Some(byte) = hex.and_then(|hex| u8::from_str_radix(hex, 16).ok()) {
+                decoded.push(byte);
+                index += 3;
+                continue;
+            }
+        }
+        decoded.push(bytes[index]);
+        index += 1;
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn get(server: &UpdateFileServer, path: &str) -> String {
+        let mut stream = TcpStream::connect(server.addr()).unwrap();
+        write!(stream, "GET /{} HTTP/1.1\r\nHost: localhost\r\n\r\n", path).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_percent_decode() {
+        assert_eq!(percent_decode("a%20b"), "a b");
+        assert_eq!(percent_decode("%E6%9B%B4%E6%96%B0"), "更新");
+        assert_eq!(percent_decode("app%2"), "app%2");
+        assert_eq!(percent_decode("100%"), "100%");
+    }
+
+    #[test]
+    fn test_serves_only_allowed_files() {
+        let dir = std::env::temp_dir().join(format!("update_server_{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(dir.join("root")).unwrap();
+        std::fs::write(dir.join("root").join("应用 0.2.0.msi"), "bundle").unwrap();
+        std::fs::write(dir.join("root").join("other.txt"), "other").unwrap();
+        std::fs::write(dir.join("secret.txt"), "secret").unwrap();
+
+        let server =
+            UpdateFileServer::start("127.0.0.1:0", ServedFiles::Directory(dir.join("root")))
+                .unwrap();
+        server.set_manifest(b"{}".to_vec());
+        assert!(get(&server, MANIFEST_FILE_NAME).ends_with("\r\n\r\n{}"));
+        assert!(get(&server, "%E5%BA%94%E7%94%A8%200.2.0.msi").ends_with("bundle"));
+        assert!(get(&server, "other.txt").ends_with("other"));
+        assert!(get(&server, "..%2Fsecret.txt").starts_with("HTTP/1.1 404"));
+        drop(server);
+
+        let server = UpdateFileServer::start(
+            "127.0.0.1:0",
+            ServedFiles::Single {
+                file_name: "应用 0.2.0.msi".to_string(),
+                path: dir.join("root").join("应用 0.2.0.msi"),
+            },
+        )
+        .unwrap();
+        assert!(get(&server, "%E5%BA%94%E7%94%A8%200.2.0.msi").ends_with("bundle"));
+        assert!(get(&server, "other.txt").starts_with("HTTP/1.1 404"));
+        // 未设置清单时不提供 latest.json
+        assert!(get(&server, MANIFEST_FILE_NAME).starts_with("HTTP/1.1 404"));
+        drop(server);
+        let _ = std::fs::remove_dir_all(dir);
+    }
+}
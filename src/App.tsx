@@ -2,7 +2,7 @@ import {useLayoutEffect, useState} from "react";
 import reactLogo from "./assets/react.svg";
 import { invoke } from "@tauri-apps/api/core";
 import "./App.css";
-import checkForAppUpdates from "./updater.tsx";
+import checkForAppUpdates, {showWhatsNew} from "./updater.tsx";
 import {getVersion} from "@tauri-apps/api/app";
 
 function App() {
@@ -20,6 +20,7 @@ function App() {
   useLayoutEffect(() => {
     checkForAppUpdates(setDownloaded, setContentLength).then(r => console.log(r));
     getVersion().then(v => setVersion(v));
+    showWhatsNew().then(r => console.log(r));
   }, [])
 
   return (
@@ -1,11 +1,21 @@
 // sample front-end code for the updater
-import { check } from '@tauri-apps/plugin-updater';
 import { ask, message } from '@tauri-apps/plugin-dialog';
 import {invoke} from "@tauri-apps/api/core";
+import {listen} from "@tauri-apps/api/event";
 import type { Dispatch, SetStateAction } from 'react';
 
+type UpdateInfo = {
+  channel: 'stable' | 'beta' | 'internal';
+  current_version: string;
+  version: string;
+  notes: string | null;
+  date: string | null;
+  mandatory: boolean;
+};
+
 async function checkForAppUpdates(setDownloaded: Dispatch<SetStateAction<number>>, setContentLength: Dispatch<SetStateAction<number>>) {
-  const update = await check();
+  // 更新渠道与更新地址由 Rust 端的 UpdateManagerState 决定
+  const update = await invoke<UpdateInfo | null>('check_for_update');
   if (update === null) {
     await message('You are on the latest version. Stay awesome!', {
       title: 'Success',
@@ -13,38 +23,47 @@ async function checkForAppUpdates(setDownloaded: Dispatch<SetStateAction<number>
       okLabel: 'OK'
     });
     return;
-  } else if (update?.available) {
-    const yes = await ask(`Update to ${update.version} is available!\n\nRelease notes: ${update.body}`, {
+  } else {
+    const yes = await ask(`Update to ${update.version} (${update.channel}) is available!\n\nRelease notes: ${update.notes ?? ''}`, {
       title: 'Update Available',
       kind: 'info',
       okLabel: 'Update',
       cancelLabel: 'Cancel'
     });
     if (yes) {
-      await update.downloadAndInstall(async (event) => {
-        switch (event.event) {
-          case 'Started':
-            setContentLength(event.data.contentLength ?? 0);
-            break;
-          case 'Progress':
-            setDownloaded(prevState => {
-              return prevState + (event.data.chunkLength ?? 0)
-            });
-
-            break;
-          case 'Finished':
-            break;
-        }
-        await invoke('relaunch');
-      })
-      // await message('Update downloaded and installed successfully!', {
-      //   title: 'Update Installed',
-      //   kind: 'info',
-      //   okLabel: 'OK'
-      // });
-      // await invoke('relaunch')
+      const unlisten = await listen<{downloaded: number, content_length: number | null}>('update_download_progress', (event) => {
+        setContentLength(event.payload.content_length ?? 0);
+        setDownloaded(event.payload.downloaded);
+      });
+      try {
+        await invoke('download_and_install_update');
+      } finally {
+        unlisten();
+      }
+      await invoke('relaunch');
     }
   }
 }
 
+type Changelog = {
+  entries: { version: string; time: string; groups: Record<string, string[]> }[];
+  groups: Record<string, string[]>;
+};
+
+// 升级后首次启动时展示一次更新内容
+export async function showWhatsNew() {
+  const changelog = await invoke<Changelog | null>('take_whats_new');
+  if (changelog === null || changelog.entries.length === 0) {
+    return;
+  }
+  const text = Object.entries(changelog.groups)
+    .map(([category, items]) => `${category}\n${items.map(item => ` - ${item}`).join('\n')}`)
+    .join('\n\n');
+  await message(text, {
+    title: "What's New",
+    kind: 'info',
+    okLabel: 'OK'
+  });
+}
+
 export default checkForAppUpdates;